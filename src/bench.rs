@@ -2,27 +2,83 @@ use core::panic;
 use std::{
     fs::{self, File},
     io::{Write, stdout},
+    path::PathBuf,
     process::Command,
     sync::{Arc, Mutex},
     thread::sleep,
     time::Duration,
 };
 
-use inquire::{Select, prompt_u32, prompt_usize};
-use rand::{rng, seq::SliceRandom};
+use inquire::{Confirm, Select, Text, prompt_u32, prompt_usize, validator::Validation};
+use rand::seq::SliceRandom;
 use threadpool::ThreadPool;
 
-use crate::{gui::SortingStrategy, sim::PushSwapSim};
+use psv::{
+    bounds::{LowerBounds, efficiency},
+    numbers::{NumberPattern, seeded_rng},
+    optimizer::optimize,
+    oracle::{MAX_ORACLE_NUMBERS, OptimalTable, permutations},
+    parser::{check_strict_format, format_instructions},
+    sim::{PushSwapInstruction, PushSwapSim, normalized_vec},
+    solvers::Solver,
+    stats::InstructionStats,
+};
 
-pub fn benchmark() {
-    let numbers = prompt_u32("Amount of numbers to sort:").expect("failed to get number");
-    let tests =
-        prompt_usize("Amount of tests to execute for benchmark:").expect("failed to get number");
+use crate::gui::SortingStrategy;
+
+#[derive(Clone)]
+enum BenchSource {
+    Executable {
+        path: PathBuf,
+        strategy: SortingStrategy,
+        strict: bool,
+    },
+    BuiltIn(Solver),
+}
+
+impl BenchSource {
+    fn instructions(&self, numbers: &[i64]) -> String {
+        match self {
+            BenchSource::Executable { path, strategy, .. } => {
+                let args: Vec<_> = numbers.iter().map(i64::to_string).collect();
+                let mut cmd = Command::new(path);
+                if *strategy != SortingStrategy::None {
+                    cmd.arg(strategy.to_arg());
+                }
+                let instructions = cmd
+                    .args(args)
+                    .output()
+                    .expect("push_swap command failed to run");
+                String::from_utf8(instructions.stdout)
+                    .expect("push_swap output is not valid utf-8 text")
+            }
+            BenchSource::BuiltIn(solver) => format_instructions(&solver.solve(numbers)),
+        }
+    }
+}
+
+fn prompt_source() -> BenchSource {
+    let mut sources = vec!["push_swap executable".to_string()];
+    sources.extend(
+        Solver::ALL
+            .iter()
+            .map(|s| format!("Built-in solver: {}", s)),
+    );
+    let source = Select::new("Instructions source:", sources)
+        .raw_prompt()
+        .expect("failed to get instructions source");
+    if source.index > 0 {
+        return BenchSource::BuiltIn(Solver::ALL[source.index - 1]);
+    }
     let strategy = Select::new("Sorting strategy:", SortingStrategy::ALL.to_vec())
         .prompt()
         .expect("failed to get sorting strategy");
+    let strict = Confirm::new("Strictly check the output format (one instruction per line)?")
+        .with_default(false)
+        .prompt()
+        .expect("failed to get strict mode");
 
-    let exec_path = if let Ok(path) = fs::canonicalize("push_swap") {
+    let path = if let Ok(path) = fs::canonicalize("push_swap") {
         println!("Found push_swap executable in current directory");
         path
     } else {
@@ -32,41 +88,156 @@ pub fn benchmark() {
             .pick_file()
             .expect("no file selected")
     };
+    BenchSource::Executable {
+        path,
+        strategy,
+        strict,
+    }
+}
 
+/// Asks for the shape of the numbers, `None` meaning a uniform shuffle.
+fn prompt_pattern() -> Option<NumberPattern> {
+    let mut options = vec!["Random".to_string()];
+    options.extend(NumberPattern::ALL.iter().map(NumberPattern::to_string));
+    let choice = Select::new("Numbers:", options)
+        .raw_prompt()
+        .expect("failed to get numbers pattern");
+    let mut pattern = choice.index.checked_sub(1).map(|i| NumberPattern::ALL[i])?;
+    if let Some((name, value)) = pattern.parameter_mut() {
+        *value = prompt_usize(format!("{}:", name)).expect("failed to get number");
+    }
+    Some(pattern)
+}
+
+/// Asks for the seed of the random numbers, picking a random one if none is given.
+fn prompt_seed() -> u64 {
+    let seed = Text::new("Seed (leave empty for a random seed):")
+        .with_validator(|s: &str| {
+            Ok(if s.trim().is_empty() || s.trim().parse::<u64>().is_ok() {
+                Validation::Valid
+            } else {
+                Validation::Invalid("The seed must be a positive integer".into())
+            })
+        })
+        .prompt()
+        .expect("failed to get seed");
+    seed.trim().parse().unwrap_or_else(|_| rand::random())
+}
+
+#[derive(Clone)]
+struct TestResult {
+    instructions: usize,
+    saved: usize,
+    no_ops: usize,
+    stats: InstructionStats,
+    optimal: Option<usize>,
+    lower_bound: usize,
+    numbers: Vec<u32>,
+}
+
+pub fn benchmark() {
+    let numbers = prompt_u32("Amount of numbers to sort:").expect("failed to get number");
+    let exhaustive = numbers as usize <= MAX_ORACLE_NUMBERS
+        && Confirm::new("Test all permutations and compare with optimal solutions?")
+            .with_default(true)
+            .prompt()
+            .expect("failed to get exhaustive mode");
+    let permutations = Arc::new(if exhaustive {
+        permutations(numbers as usize)
+    } else {
+        vec![]
+    });
+    let tests = if exhaustive {
+        permutations.len()
+    } else {
+        prompt_usize("Amount of tests to execute for benchmark:").expect("failed to get number")
+    };
+    let pattern = if exhaustive { None } else { prompt_pattern() };
+    let seed = (!exhaustive).then(prompt_seed);
+    let source = prompt_source();
+
+    if numbers as usize <= MAX_ORACLE_NUMBERS {
+        println!("Computing optimal solutions");
+    }
+    let optimal_table = Arc::new(OptimalTable::new(numbers as usize));
     let results = Arc::new(Mutex::new(vec![None; tests]));
-    let error_log = File::create("error.log").expect("cannor create error.log file");
+    let mut error_log = File::create("error.log").expect("cannor create error.log file");
+    if let Some(seed) = seed {
+        println!("Seed: {}", seed);
+        let _ = writeln!(error_log, "Seed: {}", seed);
+    }
     let error_log = Arc::new(Mutex::new(error_log));
     let pool = ThreadPool::new(4);
     for test_num in 0..tests {
         let results = results.clone();
-        let exec_path = exec_path.clone();
+        let source = source.clone();
         let error_log = error_log.clone();
+        let permutations = permutations.clone();
+        let optimal_table = optimal_table.clone();
         pool.execute(move || {
             let mut sim = PushSwapSim::default();
-            let mut numbers: Vec<_> = (0..numbers).collect();
-            numbers.shuffle(&mut rng());
-            let args: Vec<_> = numbers.iter().map(u32::to_string).collect();
-            let mut cmd = Command::new(exec_path.clone());
-            if strategy != SortingStrategy::None {
-                cmd.arg(strategy.to_arg());
+            let (numbers, original) = if let Some(permutation) = permutations.get(test_num) {
+                let original = permutation.iter().map(|n| *n as i64).collect();
+                (permutation.clone(), original)
+            } else {
+                // Every test has its own generator, so that its numbers do not
+                // depend on the order the tests run in
+                let seed = seed.unwrap_or_default().wrapping_add(test_num as u64);
+                let mut rng = seeded_rng(seed);
+                let original: Vec<i64> = match pattern {
+                    Some(pattern) => pattern.generate(numbers as usize, &mut rng),
+                    None => {
+                        let mut numbers: Vec<_> = (0..numbers as i64).collect();
+                        numbers.shuffle(&mut rng);
+                        numbers
+                    }
+                };
+                (normalized_vec(&original), original)
+            };
+            let instructions = source.instructions(&original);
+            let issues = match source {
+                BenchSource::Executable { strict: true, .. } => check_strict_format(&instructions),
+                _ => vec![],
+            };
+            if !issues.is_empty() {
+                let mut error_log = error_log.lock().expect("gimme");
+                let _ = writeln!(error_log, "Test {} failed.", test_num);
+                let _ = writeln!(error_log, "Numbers: {:?}", original);
+                let _ = writeln!(error_log, "Output format issues:");
+                for issue in &issues {
+                    let _ = writeln!(error_log, "{}", issue);
+                }
+                let _ = writeln!(error_log, "====================================");
+                drop(error_log);
+                panic!(
+                    "Test {}: {} output format issue(s), first at {}",
+                    test_num,
+                    issues.len(),
+                    issues[0]
+                );
+            }
+            if let Err(err) = sim.load_normalized(numbers.clone(), &instructions) {
+                let mut error_log = error_log.lock().expect("gimme");
+                let _ = writeln!(error_log, "Test {} failed.", test_num);
+                let _ = writeln!(error_log, "Numbers: {:?}", original);
+                let _ = writeln!(error_log, "Invalid instruction at {}", err);
+                let _ = write!(error_log, "{}", err.snippet());
+                let _ = writeln!(error_log, "====================================");
+                drop(error_log);
+                panic!(
+                    "Test {}: invalid instruction at line {}: `{}`",
+                    test_num, err.line, err.line_text
+                );
             }
-            let instructions = cmd
-                .args(args)
-                .output()
-                .expect("push_swap command failed to run");
-            let instructions = String::from_utf8(instructions.stdout)
-                .expect("push_swap output is not valid utf-8 text");
-            sim.load_normalized(numbers.clone(), &instructions)
-                .expect("invalid instructions");
             let mut extern_program_counter = 0;
             while sim.step() {
                 extern_program_counter += 1;
             }
-            sim.make_contiguous();
-            if !sim.stack_a().is_sorted() || !sim.stack_b().is_empty() {
+            if !sim.is_sorted() {
+                sim.make_contiguous();
                 let mut error_log = error_log.lock().expect("gimme");
                 let _ = writeln!(error_log, "Test {} failed.", test_num);
-                let _ = writeln!(error_log, "Numbers: {:?}", numbers);
+                let _ = writeln!(error_log, "Numbers: {:?}", original);
                 let _ = writeln!(error_log, "Instructions: {}", instructions);
                 let _ = writeln!(
                     error_log,
@@ -81,8 +252,19 @@ pub fn benchmark() {
                     test_num
                 );
             }
+            let result = TestResult {
+                instructions: extern_program_counter,
+                saved: optimize(&numbers, sim.instructions()).saved(),
+                no_ops: sim.no_ops().len(),
+                stats: InstructionStats::new(sim.instructions(), sim.amount()),
+                optimal: (*optimal_table)
+                    .as_ref()
+                    .and_then(|table| table.distance(&numbers)),
+                lower_bound: LowerBounds::new(&numbers).best(),
+                numbers,
+            };
             let mut results = results.lock().expect("panic chain!");
-            results[test_num] = Some(extern_program_counter);
+            results[test_num] = Some(result);
         });
     }
     println!("Tests running.");
@@ -108,7 +290,22 @@ pub fn benchmark() {
         println!("Testing done with no errors!");
     }
     let results = results.lock().expect("panic chain!");
-    let results: Vec<_> = results.iter().filter_map(|r| *r).collect();
+    let results: Vec<_> = results.iter().flatten().collect();
+    let saved: Vec<_> = results.iter().map(|r| r.saved).collect();
+    let no_ops: usize = results.iter().map(|r| r.no_ops).sum();
+    let mut stats = InstructionStats::default();
+    for r in &results {
+        stats.merge(&r.stats);
+    }
+    let gaps: Vec<_> = results
+        .iter()
+        .filter_map(|r| r.optimal.map(|optimal| (r.instructions - optimal, r)))
+        .collect();
+    let efficiencies: Vec<_> = results
+        .iter()
+        .map(|r| efficiency(r.optimal.unwrap_or(r.lower_bound), r.instructions))
+        .collect();
+    let results: Vec<_> = results.iter().map(|r| r.instructions).collect();
     let min = results.iter().copied().min().unwrap_or(0);
     let max = results.iter().copied().max().unwrap_or(0);
     let avg = if results.is_empty() {
@@ -123,7 +320,79 @@ pub fn benchmark() {
         avg,
         max
     );
+    let total_saved: usize = saved.iter().sum();
+    if total_saved > 0 {
+        println!(
+            "{} instructions could have been saved by merging or cancelling out adjacent instructions (Average: {}, Max: {})",
+            total_saved,
+            total_saved / saved.len(),
+            saved.iter().max().unwrap_or(&0)
+        );
+    }
+    if no_ops > 0 {
+        println!(
+            "{} instructions had no effect on the stacks and were wasted moves",
+            no_ops
+        );
+    }
+    if stats.total > 0 {
+        print_stats(&stats, results.len());
+    }
+    if let Some((gap, worst)) = gaps.iter().max_by_key(|(gap, _)| *gap) {
+        let optimal = gaps.iter().filter(|(gap, _)| *gap == 0).count();
+        println!("Optimal solutions found: {}/{}", optimal, gaps.len());
+        println!(
+            "Worst gap: +{} ({} instructions, optimal: {}) with numbers {:?}",
+            gap,
+            worst.instructions,
+            worst.instructions - gap,
+            worst.numbers
+        );
+    }
+    if !efficiencies.is_empty() {
+        let average = efficiencies.iter().sum::<f64>() / efficiencies.len() as f64;
+        let lowest = efficiencies.iter().copied().fold(f64::INFINITY, f64::min);
+        println!(
+            "Efficiency against {}: Average: {:.2}%, Lowest: {:.2}%",
+            if gaps.is_empty() {
+                "lower bound"
+            } else {
+                "optimal"
+            },
+            average * 100.,
+            lowest * 100.
+        );
+    }
+    if let Some(seed) = seed {
+        println!(
+            "Run the benchmark again with the seed {} to reproduce it",
+            seed
+        );
+    }
     println!(
         "Note: these values may change and can be more or less accurate depending on how many tests you ran."
     );
 }
+
+fn print_stats(stats: &InstructionStats, tests: usize) {
+    println!("Instruction breakdown:");
+    for ins in PushSwapInstruction::ALL {
+        let count = stats.count(ins);
+        if count > 0 {
+            println!(
+                "  {:<3} {:>10} ({:.2}%)",
+                ins.to_string(),
+                count,
+                stats.share(count) * 100.
+            );
+        }
+    }
+    println!(
+        "Combined instructions (ss, rr, rrr): {:.2}%",
+        stats.share(stats.combined()) * 100.
+    );
+    println!("Average push pairs: {}", stats.push_pairs / tests);
+    if let Some((ins, len)) = stats.longest_rotation {
+        println!("Longest rotation run: {} x {}", len, ins);
+    }
+}
@@ -0,0 +1,110 @@
+//! Lower bounds on the amount of instructions needed to sort numbers.
+
+/// Cheap lower bounds on the amount of instructions needed to sort a sequence of
+/// normalized numbers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LowerBounds {
+    /// Numbers outside of the longest increasing subsequence of any rotation of
+    /// stack A must be moved by a swap or a push. A single swap moves 2 numbers.
+    pub moved: usize,
+    /// Rotations needed if the numbers are already sorted, but rotated.
+    pub rotation: usize,
+}
+
+/// Length of the longest increasing subsequence of every prefix of the values.
+fn running_lis(values: impl Iterator<Item = u32>) -> Vec<usize> {
+    let mut tails: Vec<u32> = vec![];
+    let mut lengths = vec![0];
+    for value in values {
+        let i = tails.partition_point(|t| *t < value);
+        if i == tails.len() {
+            tails.push(value);
+        } else {
+            tails[i] = value;
+        }
+        lengths.push(tails.len());
+    }
+    lengths
+}
+
+/// Upper bound of the longest increasing subsequence among all rotations of the
+/// numbers, in `O(n log n)`: an increasing subsequence of the rotation starting at
+/// `k` is at most as long as the ones of `numbers[k..]` and `numbers[..k]` combined.
+fn cyclic_lis_upper_bound(numbers: &[u32]) -> usize {
+    let prefix = running_lis(numbers.iter().copied());
+    let mut suffix = running_lis(numbers.iter().rev().map(|n| u32::MAX - n));
+    suffix.reverse();
+    prefix
+        .iter()
+        .zip(suffix)
+        .map(|(prefix, suffix)| prefix + suffix)
+        .max()
+        .unwrap_or(0)
+        .min(numbers.len())
+}
+
+/// If the numbers are sorted once rotated, the amount of rotations to get there.
+fn rotation_distance(numbers: &[u32]) -> Option<usize> {
+    let start = numbers.iter().position(|n| *n == 0)?;
+    let sorted = numbers
+        .iter()
+        .cycle()
+        .skip(start)
+        .take(numbers.len())
+        .enumerate()
+        .all(|(i, n)| *n as usize == i);
+    sorted.then(|| start.min(numbers.len() - start))
+}
+
+impl LowerBounds {
+    /// Computes the lower bounds for the given normalized numbers, in `O(n log n)`.
+    pub fn new(numbers: &[u32]) -> Self {
+        let must_move = numbers.len() - cyclic_lis_upper_bound(numbers);
+        Self {
+            moved: must_move.div_ceil(2),
+            rotation: rotation_distance(numbers).unwrap_or(0),
+        }
+    }
+
+    /// The highest of the lower bounds.
+    pub fn best(&self) -> usize {
+        self.moved.max(self.rotation)
+    }
+}
+
+/// Ratio between a lower bound (or the optimal amount) of instructions and the
+/// actual amount of instructions, from 0 to 1.
+pub fn efficiency(bound: usize, instructions: usize) -> f64 {
+    if instructions == 0 {
+        1.
+    } else {
+        bound as f64 / instructions as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LowerBounds;
+    use crate::oracle::{OptimalTable, permutations};
+
+    #[test]
+    fn bounds_never_exceed_optimal() {
+        for amount in 1..=6 {
+            let table = OptimalTable::new(amount).unwrap();
+            for numbers in permutations(amount) {
+                let bound = LowerBounds::new(&numbers).best();
+                let optimal = table.distance(&numbers).unwrap();
+                assert!(bound <= optimal, "{:?}: {} > {}", numbers, bound, optimal);
+            }
+        }
+    }
+
+    #[test]
+    fn rotated() {
+        let bounds = LowerBounds::new(&[3, 4, 5, 6, 0, 1, 2]);
+        assert_eq!(bounds.rotation, 3);
+        assert_eq!(bounds.moved, 0);
+        assert_eq!(LowerBounds::new(&[0, 1, 2, 3]).best(), 0);
+        assert!(LowerBounds::new(&[9, 8, 7, 6, 5, 4, 3, 2, 1, 0]).moved >= 4);
+    }
+}
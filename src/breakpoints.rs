@@ -0,0 +1,119 @@
+//! Conditions to pause playback on.
+
+use std::fmt::Display;
+
+use crate::{
+    progress::ProgressStep,
+    sim::{PushSwapInstruction, PushSwapSim},
+};
+
+/// A condition checked after every executed instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Hit once the given amount of instructions were executed.
+    ProgramCounter(usize),
+    /// Hit when the last number of stack B is pushed to stack A.
+    StackBEmpty,
+    /// Hit when the given normalized number is pushed to stack B.
+    PushedToB(u32),
+    /// Hit when stack A becomes sorted, whether stack B is empty or not.
+    StackASorted,
+    /// Hit when the last executed instructions match the given ones.
+    Pattern(Vec<PushSwapInstruction>),
+}
+
+fn a_sorted(step: &ProgressStep) -> bool {
+    step.size_a > 0 && step.sorted_prefix == step.size_a
+}
+
+impl Breakpoint {
+    /// Checks whether the instruction that was just executed by the simulation
+    /// hits the breakpoint. `progress` must be computed from the same simulation
+    /// (see [`crate::progress::progress`]).
+    pub fn is_hit(&self, sim: &PushSwapSim, progress: &[ProgressStep]) -> bool {
+        let pc = sim.program_counter();
+        if pc == 0 {
+            return false;
+        }
+        let last = sim.instructions()[pc - 1];
+        let effective = !sim.is_no_op(pc - 1);
+        let (stack_a, stack_b) = sim.stacks();
+        match self {
+            Breakpoint::ProgramCounter(counter) => pc == *counter,
+            Breakpoint::StackBEmpty => {
+                last == PushSwapInstruction::PushA && effective && stack_b.is_empty()
+            }
+            Breakpoint::PushedToB(n) => {
+                last == PushSwapInstruction::PushB && effective && stack_b.front() == Some(n)
+            }
+            Breakpoint::StackASorted => match progress.get(pc - 1..=pc) {
+                Some([before, after]) => !a_sorted(before) && a_sorted(after),
+                _ => stack_a.iter().is_sorted(),
+            },
+            Breakpoint::Pattern(pattern) => {
+                !pattern.is_empty() && sim.instructions()[..pc].ends_with(pattern)
+            }
+        }
+    }
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Breakpoint::ProgramCounter(counter) => write!(f, "Program counter reaches {}", counter),
+            Breakpoint::StackBEmpty => write!(f, "Stack B becomes empty"),
+            Breakpoint::PushedToB(n) => write!(f, "{} is pushed to stack B", n),
+            Breakpoint::StackASorted => write!(f, "Stack A becomes sorted"),
+            Breakpoint::Pattern(pattern) => {
+                let pattern: Vec<_> = pattern.iter().map(|ins| ins.to_string()).collect();
+                write!(f, "Pattern `{}` appears", pattern.join(" "))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Breakpoint;
+    use crate::{parser::parse_push_swap, progress::progress, sim::PushSwapSim};
+
+    fn hits(breakpoint: Breakpoint, numbers: Vec<u32>, instructions: &str) -> Vec<usize> {
+        let mut sim = PushSwapSim::default();
+        sim.load_normalized(numbers, instructions).unwrap();
+        let progress = progress(&sim);
+        let mut hits = vec![];
+        while sim.step() {
+            if breakpoint.is_hit(&sim, &progress) {
+                hits.push(sim.program_counter());
+            }
+        }
+        hits
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let numbers = vec![2, 0, 1, 3];
+        let instructions = "pb pb pa pa pa sa pb ra rra pa";
+        assert_eq!(
+            hits(Breakpoint::ProgramCounter(3), numbers.clone(), instructions),
+            [3]
+        );
+        assert_eq!(
+            hits(Breakpoint::StackBEmpty, numbers.clone(), instructions),
+            [4, 10]
+        );
+        assert_eq!(
+            hits(Breakpoint::PushedToB(0), numbers.clone(), instructions),
+            [2, 7]
+        );
+        assert_eq!(
+            hits(Breakpoint::StackASorted, numbers.clone(), instructions),
+            [1]
+        );
+        let pattern = parse_push_swap("pa pa").unwrap();
+        assert_eq!(
+            hits(Breakpoint::Pattern(pattern), numbers, instructions),
+            [4, 5]
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use std::io::{Read, stdin};
+
+use psv::{numbers::parse_numbers, sim::PushSwapSim};
+
+/// Parses push_swap arguments, which may each contain several numbers.
+/// Returns `None` if an argument is empty, or if a number is invalid or duplicated.
+pub fn parse_args(args: &[String]) -> Option<Vec<i64>> {
+    if args.iter().any(|arg| arg.trim().is_empty()) {
+        return None;
+    }
+    parse_numbers(&args.join(" ")).ok()
+}
+
+fn error() -> i32 {
+    eprintln!("Error");
+    1
+}
+
+/// Runs the instructions on the numbers, returning whether they sort them, or
+/// `None` if the instructions are not exactly one valid instruction per line,
+/// each terminated by a `\n`.
+fn sorts(numbers: &[i64], instructions: &str) -> Option<bool> {
+    let lines: Vec<_> = match instructions.strip_suffix('\n') {
+        Some(instructions) => instructions.split('\n').collect(),
+        None if instructions.is_empty() => vec![],
+        None => return None,
+    };
+    let mut sim = PushSwapSim::default();
+    sim.load_random(numbers, instructions).ok()?;
+    // Every line must be the exact name of its instruction, without any whitespace
+    let names = sim.instructions().iter().map(ToString::to_string);
+    if !names.eq(lines) {
+        return None;
+    }
+    while sim.step() {}
+    Some(sim.is_sorted())
+}
+
+/// Behaves like the 42 `checker` program: reads instructions from stdin and
+/// prints `OK` if they sort the given numbers, `KO` if they don't, or `Error`
+/// on invalid arguments or instructions. Returns the process exit code.
+pub fn check(args: &[String]) -> i32 {
+    if args.is_empty() {
+        return 0;
+    }
+    let Some(numbers) = parse_args(args) else {
+        return error();
+    };
+    let mut instructions = String::new();
+    if stdin().read_to_string(&mut instructions).is_err() {
+        return error();
+    }
+    match sorts(&numbers, &instructions) {
+        Some(true) => println!("OK"),
+        Some(false) => println!("KO"),
+        None => return error(),
+    }
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use super::sorts;
+
+    #[test]
+    fn test_sorts() {
+        let numbers = [2, 1, 3];
+        assert_eq!(sorts(&numbers, "sa\n"), Some(true));
+        assert_eq!(sorts(&numbers, "ra\n"), Some(false));
+        assert_eq!(sorts(&[1, 2], ""), Some(true));
+        for instructions in [
+            "sa sb\n",
+            "sa \n",
+            "sa\r\n",
+            "\n",
+            "sa\n\nra\n",
+            "sa",
+            "sx\n",
+        ] {
+            assert_eq!(sorts(&numbers, instructions), None, "{:?}", instructions);
+        }
+    }
+}
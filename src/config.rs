@@ -2,6 +2,8 @@ use std::{fmt::Display, fs::File, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 
+use psv::{presets::UserPreset, sim::DEFAULT_KEYFRAME_MEMORY};
+
 use crate::{gradient::Gradient, util};
 
 const CONFIG_FILENAME: &str = ".psvconf.json";
@@ -46,6 +48,14 @@ pub struct Config {
     pub push_swap_path: Option<PathBuf>,
     pub current_profile: usize,
     pub color_profiles: Vec<ColorProfile>,
+    #[serde(default = "default_keyframe_memory_mib")]
+    pub keyframe_memory_mib: usize,
+    #[serde(default)]
+    pub user_presets: Vec<UserPreset>,
+}
+
+fn default_keyframe_memory_mib() -> usize {
+    DEFAULT_KEYFRAME_MEMORY / (1024 * 1024)
 }
 
 impl Default for Config {
@@ -56,6 +66,8 @@ impl Default for Config {
             push_swap_path: None,
             color_profiles: vec![util::default_profile()],
             current_profile: 0,
+            keyframe_memory_mib: default_keyframe_memory_mib(),
+            user_presets: vec![],
         }
     }
 }
@@ -0,0 +1,295 @@
+//! Error management checks of push_swap programs, run against a catalog of invalid arguments.
+//!
+//! On invalid arguments, push_swap must print `Error` followed by a newline on its standard
+//! error, print nothing on its standard output and exit with a non-zero code. Without
+//! arguments, or with valid numbers that are already sorted, it must print nothing at all
+//! and exit successfully.
+
+use std::{
+    fmt::Display,
+    io::{self, Read},
+    path::Path,
+    process::{Command, Stdio},
+    thread::{self, sleep},
+    time::{Duration, Instant},
+};
+
+/// Arguments given to a push_swap program, along with the expected behavior.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCase {
+    /// Short description of the case.
+    pub name: &'static str,
+    /// Arguments given to the program.
+    pub args: &'static [&'static str],
+    /// Whether the program must report an error, or print nothing and exit successfully.
+    pub expects_error: bool,
+}
+
+const fn invalid(name: &'static str, args: &'static [&'static str]) -> ErrorCase {
+    ErrorCase {
+        name,
+        args,
+        expects_error: true,
+    }
+}
+
+const fn sorted(name: &'static str, args: &'static [&'static str]) -> ErrorCase {
+    ErrorCase {
+        name,
+        args,
+        expects_error: false,
+    }
+}
+
+/// Every case checked by [`run_suite`].
+pub const ERROR_CASES: &[ErrorCase] = &[
+    invalid("Duplicate numbers", &["1", "2", "1"]),
+    invalid("Duplicate numbers with leading zeros", &["1", "2", "001"]),
+    invalid("Duplicate zero with a sign", &["0", "-0"]),
+    invalid("Above INT_MAX", &["1", "2147483648"]),
+    invalid("Below INT_MIN", &["-2147483649", "1"]),
+    invalid("Far above INT_MAX", &["1", "99999999999999999999"]),
+    invalid("Lone plus sign", &["1", "+", "2"]),
+    invalid("Lone minus sign", &["1", "-", "2"]),
+    invalid("Double dash", &["1", "--", "2"]),
+    invalid("Double sign", &["1", "--2"]),
+    invalid("Trailing letter", &["2", "1a"]),
+    invalid("Empty string", &["1", ""]),
+    invalid("Whitespace only", &["1", "   "]),
+    invalid("Quoted numbers with a duplicate", &["3 2 3"]),
+    invalid("Quoted numbers with a letter", &["1 2 x"]),
+    sorted("Leading zeros", &["007"]),
+    sorted("Sorted numbers with leading zeros", &["-007", "0010"]),
+    sorted("No arguments", &[]),
+];
+
+/// How a program exited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    /// The program exited on its own with this code.
+    Code(i32),
+    /// The program was killed by a signal, e.g. after a segmentation fault.
+    Crashed,
+    /// The program was killed after running for too long.
+    TimedOut,
+}
+
+/// What a program printed and how it exited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseOutput {
+    /// Standard output of the program.
+    pub stdout: Vec<u8>,
+    /// Standard error of the program.
+    pub stderr: Vec<u8>,
+    /// Exit status of the program.
+    pub exit: Exit,
+}
+
+/// Reason a program did not behave as expected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaseFailure {
+    /// The standard output was not empty.
+    Stdout(String),
+    /// The standard error was not the expected one.
+    Stderr(String),
+    /// The exit status was not the expected one.
+    Exit(Exit),
+}
+
+impl Display for CaseFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaseFailure::Stdout(out) => write!(f, "printed {:?} on stdout", out),
+            CaseFailure::Stderr(err) => write!(f, "printed {:?} on stderr", err),
+            CaseFailure::Exit(Exit::Code(code)) => write!(f, "exited with code {}", code),
+            CaseFailure::Exit(Exit::Crashed) => write!(f, "crashed"),
+            CaseFailure::Exit(Exit::TimedOut) => write!(f, "timed out"),
+        }
+    }
+}
+
+impl ErrorCase {
+    /// The arguments as they would be typed in a shell.
+    pub fn command_line(&self) -> String {
+        let args: Vec<_> = self
+            .args
+            .iter()
+            .map(|arg| {
+                if arg.is_empty() || arg.contains(' ') {
+                    format!("\"{}\"", arg)
+                } else {
+                    arg.to_string()
+                }
+            })
+            .collect();
+        args.join(" ")
+    }
+
+    /// Lists everything the program did wrong, if anything.
+    pub fn check(&self, output: &CaseOutput) -> Vec<CaseFailure> {
+        let mut failures = vec![];
+        if !output.stdout.is_empty() {
+            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+            failures.push(CaseFailure::Stdout(stdout));
+        }
+        let expected_stderr: &[u8] = if self.expects_error { b"Error\n" } else { b"" };
+        if output.stderr != expected_stderr {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            failures.push(CaseFailure::Stderr(stderr));
+        }
+        let exit_ok = match output.exit {
+            Exit::Code(code) => (code != 0) == self.expects_error,
+            Exit::Crashed | Exit::TimedOut => false,
+        };
+        if !exit_ok {
+            failures.push(CaseFailure::Exit(output.exit));
+        }
+        failures
+    }
+}
+
+/// Outcome of a case of the suite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseResult {
+    /// The case that was run.
+    pub case: ErrorCase,
+    /// Everything the program did wrong, empty if it passed.
+    pub failures: Vec<CaseFailure>,
+}
+
+impl CaseResult {
+    /// Whether the program behaved as expected.
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Runs a push_swap program against every case of [`ERROR_CASES`], killing it
+/// after the timeout on each case.
+pub fn run_suite(path: &Path, timeout: Duration) -> io::Result<Vec<CaseResult>> {
+    ERROR_CASES
+        .iter()
+        .map(|case| {
+            let output = run_case(path, case, timeout)?;
+            Ok(CaseResult {
+                case: *case,
+                failures: case.check(&output),
+            })
+        })
+        .collect()
+}
+
+/// Runs a push_swap program with the arguments of a case, killing it after the timeout.
+pub fn run_case(path: &Path, case: &ErrorCase, timeout: Duration) -> io::Result<CaseOutput> {
+    let mut child = Command::new(path)
+        .args(case.args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Both outputs are read in the background so that the program never blocks on a full pipe
+    let read = |mut pipe: Box<dyn Read + Send>| {
+        thread::spawn(move || {
+            let mut output = vec![];
+            let _ = pipe.read_to_end(&mut output);
+            output
+        })
+    };
+    let stdout = read(Box::new(child.stdout.take().expect("stdout is piped")));
+    let stderr = read(Box::new(child.stderr.take().expect("stderr is piped")));
+    let start = Instant::now();
+    let exit = loop {
+        if let Some(status) = child.try_wait()? {
+            break status.code().map_or(Exit::Crashed, Exit::Code);
+        }
+        if start.elapsed() > timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            break Exit::TimedOut;
+        }
+        sleep(Duration::from_millis(5));
+    };
+    Ok(CaseOutput {
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+        exit,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CaseFailure, CaseOutput, ERROR_CASES, Exit};
+
+    fn output(stdout: &str, stderr: &str, exit: Exit) -> CaseOutput {
+        CaseOutput {
+            stdout: stdout.into(),
+            stderr: stderr.into(),
+            exit,
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let invalid = ERROR_CASES[0];
+        let no_args = *ERROR_CASES.last().unwrap();
+        assert!(
+            invalid
+                .check(&output("", "Error\n", Exit::Code(1)))
+                .is_empty()
+        );
+        assert!(
+            invalid
+                .check(&output("", "Error\n", Exit::Code(255)))
+                .is_empty()
+        );
+        assert_eq!(
+            invalid.check(&output("sa\n", "", Exit::Code(0))),
+            [
+                CaseFailure::Stdout("sa\n".into()),
+                CaseFailure::Stderr(String::new()),
+                CaseFailure::Exit(Exit::Code(0))
+            ]
+        );
+        assert_eq!(
+            invalid.check(&output("", "Error", Exit::Crashed)),
+            [
+                CaseFailure::Stderr("Error".into()),
+                CaseFailure::Exit(Exit::Crashed)
+            ]
+        );
+        assert!(no_args.check(&output("", "", Exit::Code(0))).is_empty());
+        assert_eq!(
+            no_args.check(&output("", "Error\n", Exit::Code(1))),
+            [
+                CaseFailure::Stderr("Error\n".into()),
+                CaseFailure::Exit(Exit::Code(1))
+            ]
+        );
+        assert_eq!(ERROR_CASES[12].command_line(), "1 \"   \"");
+    }
+
+    // The fake push_swap is a shell script
+    #[cfg(unix)]
+    #[test]
+    fn test_run_suite() {
+        use std::{fs, os::unix::fs::PermissionsExt, time::Duration};
+
+        use super::run_suite;
+
+        // Rejects everything except the valid cases of the suite
+        let script = "#!/bin/sh\ncase \"$*\" in\n\"\" | 007 | \"-007 0010\") exit 0 ;;\n\
+            \"1 - 2\") echo sa ;;\nesac\necho Error >&2\nexit 1\n";
+        let path = std::env::temp_dir().join(format!("psv-push-swap-{}.sh", std::process::id()));
+        fs::write(&path, script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        let results = run_suite(&path, Duration::from_secs(5));
+        fs::remove_file(&path).unwrap();
+        let failed: Vec<_> = results
+            .unwrap()
+            .into_iter()
+            .filter(|result| !result.passed())
+            .map(|result| result.case.name)
+            .collect();
+        assert_eq!(failed, ["Lone minus sign"]);
+    }
+}
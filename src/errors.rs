@@ -0,0 +1,48 @@
+use std::{path::PathBuf, time::Duration};
+
+use psv::conformance::run_suite;
+
+use crate::util;
+
+pub const CASE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runs a push_swap executable against every invalid argument case and
+/// reports which ones it handles correctly. The executable is the first
+/// argument, or the one detected in the current directory. Returns the process
+/// exit code.
+pub fn check_errors(args: &[String]) -> i32 {
+    let Some(path) = args
+        .first()
+        .map(PathBuf::from)
+        .or_else(util::detect_push_swap)
+    else {
+        eprintln!("Error: no push_swap executable found, give its path as an argument");
+        return 1;
+    };
+    println!("Checking error management of {}", path.display());
+    let results = match run_suite(&path, CASE_TIMEOUT) {
+        Ok(results) => results,
+        Err(err) => {
+            eprintln!("Error: failed to run {}: {}", path.display(), err);
+            return 1;
+        }
+    };
+    for result in &results {
+        let case = result.case;
+        let description = match case.command_line() {
+            line if line.is_empty() => case.name.to_string(),
+            line => format!("{}: {}", case.name, line),
+        };
+        if result.passed() {
+            println!("[PASS] {}", description);
+        } else {
+            println!("[FAIL] {}", description);
+            for failure in &result.failures {
+                println!("         {}", failure);
+            }
+        }
+    }
+    let passed = results.iter().filter(|result| result.passed()).count();
+    println!("{}/{} cases passed", passed, results.len());
+    if passed == results.len() { 0 } else { 1 }
+}
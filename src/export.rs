@@ -0,0 +1,39 @@
+use std::io::{BufWriter, Read, stdin, stdout};
+
+use psv::{
+    sim::PushSwapSim,
+    trace::{TraceFormat, write_trace},
+};
+
+use crate::check::parse_args;
+
+/// Reads instructions from stdin and writes the state of the stacks after every
+/// instruction to stdout, as JSON Lines or as CSV with `--csv`. Returns the
+/// process exit code.
+pub fn export_trace(args: &[String]) -> i32 {
+    let (format, args) = match args.first().map(String::as_str) {
+        Some("--csv") => (TraceFormat::Csv, &args[1..]),
+        Some("--jsonl") => (TraceFormat::JsonLines, &args[1..]),
+        _ => (TraceFormat::JsonLines, args),
+    };
+    let Some(numbers) = parse_args(args) else {
+        eprintln!("Error: invalid numbers");
+        return 1;
+    };
+    let mut instructions = String::new();
+    if let Err(err) = stdin().read_to_string(&mut instructions) {
+        eprintln!("Error: failed to read instructions: {}", err);
+        return 1;
+    }
+    let mut sim = PushSwapSim::default();
+    if let Err(err) = sim.load_random(&numbers, &instructions) {
+        eprintln!("Error: {}", err);
+        eprint!("{}", err.snippet());
+        return 1;
+    }
+    if let Err(err) = write_trace(&sim, format, BufWriter::new(stdout().lock())) {
+        eprintln!("Error: failed to write trace: {}", err);
+        return 1;
+    }
+    0
+}
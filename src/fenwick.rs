@@ -0,0 +1,49 @@
+//! Fenwick tree over a set of normalized numbers.
+
+/// Counts the numbers of a set of normalized numbers below a value.
+#[derive(Debug, Clone)]
+pub(crate) struct Fenwick(Vec<usize>);
+
+impl Fenwick {
+    /// Creates an empty set of numbers from 0 to `size - 1`.
+    pub(crate) fn new(size: usize) -> Self {
+        Self(vec![0; size + 1])
+    }
+
+    /// Adds a value to the set, or removes it with a negative delta.
+    pub(crate) fn add(&mut self, value: u32, delta: isize) {
+        let mut i = value as usize + 1;
+        while i < self.0.len() {
+            self.0[i] = self.0[i].wrapping_add_signed(delta);
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// Amount of numbers strictly below the value.
+    pub(crate) fn below(&self, value: u32) -> usize {
+        let mut i = value as usize;
+        let mut count = 0;
+        while i > 0 {
+            count += self.0[i];
+            i -= i & i.wrapping_neg();
+        }
+        count
+    }
+
+    /// The `n`-th smallest number of the set, starting at 0.
+    pub(crate) fn nth(&self, n: usize) -> u32 {
+        // Finds the last position whose prefix holds at most `n` numbers
+        let mut position = 0;
+        let mut left = n;
+        let mut step = self.0.len().next_power_of_two();
+        while step > 0 {
+            let next = position + step;
+            if next < self.0.len() && self.0[next] <= left {
+                position = next;
+                left -= self.0[next];
+            }
+            step /= 2;
+        }
+        position as u32
+    }
+}
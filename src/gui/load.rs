@@ -1,7 +1,7 @@
 use std::{
     fmt::{Display, Write},
-    fs,
-    io::Read,
+    fs::{self, File},
+    io::{BufWriter, Read},
     os::fd::AsRawFd,
     path::PathBuf,
     process::{Command, Stdio},
@@ -9,18 +9,28 @@ use std::{
     time::{Duration, Instant},
 };
 
-use egui::{ComboBox, Context, DragValue, ScrollArea, Widget, Window};
+use egui::{Color32, ComboBox, Context, DragValue, ScrollArea, Slider, Ui, Widget, Window};
+use egui_double_slider::DoubleSlider;
 use tokio::sync::oneshot::{Receiver, Sender, channel};
 use tokio_util::sync::CancellationToken;
 
-use crate::{
-    config::Config,
-    numbers::{DisorderSettings, NUMBER_PRESETS, NumberGeneration, compute_disorder},
+use psv::{
+    conformance::{CaseResult, run_suite},
+    numbers::{
+        DisorderSettings, NUMBER_PRESETS, NumberGeneration, NumberPattern, check_numbers,
+        compute_disorder, parse_numbers, parse_numbers_text,
+    },
+    parser::{check_strict_format, format_instructions},
+    presets::{PresetFormat, UserPreset, merge_presets, read_presets, write_presets},
     sim::PushSwapSim,
+    solvers::Solver,
 };
 
+use crate::{config::Config, errors::CASE_TIMEOUT};
+
 const RANGE_MIN: i64 = i16::MIN as i64;
 const RANGE_MAX: i64 = i16::MAX as i64;
+const MAX_REPORTED_ISSUES: usize = 20;
 
 #[derive(Default, PartialEq, Clone, Copy)]
 pub enum SortingStrategy {
@@ -72,7 +82,9 @@ enum InstructionsSource {
     Executable {
         path: Option<PathBuf>,
         mode: SortingStrategy,
+        strict: bool,
     },
+    BuiltIn(Solver),
 }
 
 impl Display for InstructionsSource {
@@ -81,15 +93,20 @@ impl Display for InstructionsSource {
             InstructionsSource::Manual(_) => "User Input",
             InstructionsSource::File(_) => "From File",
             InstructionsSource::Executable { .. } => "Program Output",
+            InstructionsSource::BuiltIn(_) => "Built-in Solver",
         };
         write!(f, "{}", str)
     }
 }
 
+/// The main simulation, and the one to compare it with if any.
+type LoadedSims = (PushSwapSim, Option<PushSwapSim>);
+
 struct AsyncWorker {
-    receiver: Receiver<Result<PushSwapSim, String>>,
+    receiver: Receiver<Result<LoadedSims, String>>,
     token: CancellationToken,
     start_time: Instant,
+    seed: Option<u64>,
 }
 
 enum ExecutionTimeInfo {
@@ -102,14 +119,266 @@ enum ExecutionTimeInfo {
 pub struct LoadingOptions {
     gen_opt: NumberGeneration,
     source_opt: InstructionsSource,
+    compare: bool,
+    compare_opt: InstructionsSource,
     worker: Option<AsyncWorker>,
     gen_time: ExecutionTimeInfo,
     disorder: Option<f64>,
     number_args: String,
+    fixed_seed: bool,
+    seed_text: String,
+    last_seed: Option<u64>,
+    preset_name: String,
+    error_suite: Option<Receiver<Result<Vec<CaseResult>, String>>>,
+    error_report: Option<Result<Vec<CaseResult>, String>>,
+}
+
+fn seed_ui(seed: &mut Option<u64>, fixed_seed: &mut bool, seed_text: &mut String, ui: &mut Ui) {
+    ui.horizontal(|ui| {
+        ui.checkbox(fixed_seed, "Fixed seed").on_hover_text("The same numbers will be generated every time. Otherwise, a new random seed is picked on every Visualize.");
+        if *fixed_seed {
+            ui.text_edit_singleline(seed_text);
+        }
+    });
+    *seed = if *fixed_seed {
+        seed_text.trim().parse().ok()
+    } else {
+        None
+    };
+    if *fixed_seed && seed.is_none() {
+        ui.colored_label(
+            ui.visuals().error_fg_color,
+            format!(
+                "Invalid seed, it must be a number from 0 to {}. A random seed will be used.",
+                u64::MAX
+            ),
+        );
+    }
+}
+
+fn disorder_ui(disorder: &mut DisorderSettings, ui: &mut Ui, amount: usize) {
+    ui.checkbox(&mut disorder.enabled, "Generate with target disorder");
+    if disorder.enabled {
+        ui.checkbox(&mut disorder.exact, "Exact disorder").on_hover_text("Numbers are built to have exactly the target disorder, picked uniformly among all the sequences that have it. Unlike random swaps, this is always fast.");
+    }
+    if disorder.enabled && disorder.exact {
+        ui.label("Target Disorder:");
+        ui.horizontal(|ui| {
+            Slider::new(&mut disorder.target, 0.0..=1.0)
+                .show_value(false)
+                .ui(ui);
+            let pairs = amount * amount.saturating_sub(1) / 2;
+            ui.label(format!(
+                "{:.2}% ({} inversions)",
+                disorder.target * 100.,
+                (disorder.target * pairs as f64).round()
+            ));
+        });
+    } else if disorder.enabled {
+        ui.checkbox(&mut disorder.shuffle, "Shuffle before matching disorder");
+        let (mut start, mut end) = (*disorder.range.start(), *disorder.range.end());
+        ui.horizontal(|ui| {
+            DragValue::new(&mut disorder.min_swaps).ui(ui);
+            ui.label("Minimum amount of swaps");
+        });
+        ui.label("Target Disorder:");
+        ui.horizontal(|ui| {
+            DoubleSlider::new(&mut start, &mut end, 0.0..=1.0)
+                .separation_distance(if amount > 1 { 1. / amount as f64 } else { 1.0 })
+                .ui(ui);
+            ui.label(format!(
+                "Min: {:.2}%, Max: {:.2}%",
+                start * 100.,
+                end * 100.
+            ));
+        });
+        ui.scope(|ui| {
+            let dark = ui.style().visuals.dark_mode;
+            ui.style_mut().visuals.override_text_color = Some(if dark {
+                Color32::from_rgb(192, 192, 0)
+            } else {
+                Color32::from_rgb(140, 140, 0)
+            });
+            ui.label("⚠ Depending on the disorder settings (and bad RNG), numbers may take some time to generate, or could even never generate. If so, press the Kill button and revise your settings.");
+        });
+        disorder.range = start..=end;
+    }
+}
+
+/// Shows the settings of an instructions source. Returns the path of the
+/// executable whose error management should be checked, if requested.
+fn source_ui(
+    ui: &mut Ui,
+    source_opt: &mut InstructionsSource,
+    config: &mut Config,
+) -> Option<PathBuf> {
+    let mut check_errors = None;
+    ComboBox::from_label("Instructions Source")
+        .selected_text(source_opt.to_string())
+        .show_ui(ui, |ui| {
+            use InstructionsSource::*;
+            let (ins, file_path, exe_path) = match &*source_opt {
+                Manual(i) => (i.clone(), None, None),
+                File(p) => (String::new(), p.clone(), None),
+                Executable { path, .. } => (String::new(), None, path.clone()),
+                BuiltIn(_) => (String::new(), None, None),
+            };
+            ui.selectable_value(source_opt, Manual(ins), "User Input").on_hover_text("You will be able to input a list of push_swap instructions yourself.");
+            ui.selectable_value(source_opt, File(file_path), "From File").on_hover_text("The selected file's contents will be interpreted as a list of push_swap instructions.");
+            ui.selectable_value(source_opt, Executable { path: exe_path, mode: Default::default(), strict: false }, "Program Output").on_hover_text("The selected program will be executed with the generated numbers above fed as input to the program. The output of the program will be interpreted as a list of push_swap instructions.");
+            ui.selectable_value(source_opt, BuiltIn(Solver::default()), "Built-in Solver").on_hover_text("Instructions will be generated by one of psv's reference sorting algorithms. Useful to compare your program with a known-good baseline.");
+        });
+    match source_opt {
+        InstructionsSource::Manual(i) => {
+            ui.label("Type push_swap instructions below");
+            ScrollArea::vertical().show(ui, |ui| {
+                ui.add_sized([300., 5.], egui::TextEdit::multiline(i));
+            });
+        }
+        InstructionsSource::File(p) => {
+            ui.horizontal(|ui| {
+                if ui.button("Browse").clicked() {
+                    let path = rfd::FileDialog::new().set_title("Select file").pick_file();
+                    if let Some(path) = path {
+                        *p = Some(path);
+                    }
+                }
+                let path = p
+                    .clone()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or("None".into());
+                ui.label(format!("Selected File: {}", path));
+            });
+        }
+        InstructionsSource::Executable { path, mode, strict } => {
+            ui.horizontal(|ui| {
+                if ui.button("Browse").clicked() {
+                    let p = rfd::FileDialog::new()
+                        .set_title("Select push_swap executable")
+                        .pick_file();
+                    if let Some(p) = p {
+                        *path = Some(p.clone());
+                        config.push_swap_path = Some(p);
+                        config.save()
+                    }
+                }
+                let path = path
+                    .clone()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or("None".into());
+                ui.label(format!("Selected Program: {}", path));
+            });
+            ComboBox::from_label("Sorting Strategy")
+                .selected_text(mode.to_string())
+                .show_ui(ui, |ui| {
+                    for m in SortingStrategy::ALL {
+                        ui.selectable_value(mode, m, m.to_string());
+                    }
+                });
+            ui.checkbox(strict, "Strict output format").on_hover_text("The program output must contain exactly one instruction per line, each followed by a newline, like the 42 checker expects. Every deviation will be reported.");
+            let check = ui.add_enabled(path.is_some(), egui::Button::new("Check error management")).on_hover_text("Runs the program against a catalog of invalid arguments, and checks that it prints `Error` on the standard error, nothing on the standard output, and exits with an error code.");
+            if check.clicked() {
+                check_errors = path.clone();
+            }
+        }
+        InstructionsSource::BuiltIn(solver) => {
+            ComboBox::from_label("Solver")
+                .selected_text(solver.to_string())
+                .show_ui(ui, |ui| {
+                    for s in Solver::ALL {
+                        ui.selectable_value(solver, s, s.to_string())
+                            .on_hover_text(s.description());
+                    }
+                });
+        }
+    };
+    check_errors
+}
+
+fn error_dialog(title: &str, description: String) {
+    rfd::MessageDialog::new()
+        .set_level(rfd::MessageLevel::Error)
+        .set_title(title)
+        .set_description(description)
+        .set_buttons(rfd::MessageButtons::Ok)
+        .show();
 }
 
-fn update_projection(projection: &mut cgmath::Matrix4<f32>, num_range: f32) {
-    *projection = cgmath::ortho(0., num_range * 2., num_range, 0., -1., 1.);
+fn preset_dialog(title: &str) -> rfd::FileDialog {
+    let mut dialog = rfd::FileDialog::new().set_title(title);
+    for format in PresetFormat::ALL {
+        dialog = dialog.add_filter(format.to_string(), &[format.extension()]);
+    }
+    dialog
+}
+
+/// Adds the presets of a file picked by the user to the config.
+fn import_presets(config: &mut Config) {
+    let Some(path) = preset_dialog("Import presets").pick_file() else {
+        return;
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) => {
+            return error_dialog("Import Error", format!("Failed to read presets: {}", err));
+        }
+    };
+    match read_presets(&text, PresetFormat::from_path(&path)) {
+        Ok(presets) => {
+            merge_presets(&mut config.user_presets, presets);
+            config.save();
+        }
+        Err(err) => error_dialog("Import Error", format!("Failed to import presets: {}", err)),
+    }
+}
+
+fn export_presets(presets: &[UserPreset]) {
+    let Some(path) = preset_dialog("Export presets")
+        .set_file_name(format!("presets.{}", PresetFormat::default().extension()))
+        .save_file()
+    else {
+        return;
+    };
+    let format = PresetFormat::from_path(&path);
+    let res =
+        File::create(&path).and_then(|file| write_presets(presets, format, BufWriter::new(file)));
+    if let Err(err) = res {
+        error_dialog("Export Error", format!("Failed to export presets: {}", err));
+    }
+}
+
+fn saved_preset_ui(ui: &mut Ui, preset: &mut UserPreset, config: &mut Config) {
+    if config.user_presets.is_empty() {
+        ui.label("No saved presets yet. Visualize some numbers and save them, or import a file.");
+    } else {
+        ComboBox::from_label("Saved Preset")
+            .selected_text(preset.name.as_str())
+            .show_ui(ui, |ui| {
+                for p in &config.user_presets {
+                    if ui.selectable_label(p == preset, p.name.as_str()).clicked() {
+                        *preset = p.clone();
+                    }
+                }
+            });
+        ui.label(format!("{} numbers", preset.numbers.len()));
+    }
+    ui.horizontal(|ui| {
+        let saved = config.user_presets.iter().position(|p| p.name == preset.name);
+        if ui.add_enabled(saved.is_some(), egui::Button::new("Delete")).clicked() && let Some(i) = saved {
+            config.user_presets.remove(i);
+            config.save();
+            *preset = config.user_presets.first().cloned().unwrap_or_default();
+        }
+        if ui.button("Import").on_hover_text("Adds the presets of a JSON or plain text file. Presets with the same name are replaced.").clicked() {
+            import_presets(config);
+            if preset.numbers.is_empty() && let Some(first) = config.user_presets.first() {
+                *preset = first.clone();
+            }
+        }
+        if ui.add_enabled(!config.user_presets.is_empty(), egui::Button::new("Export")).on_hover_text("Saves every preset to a JSON or plain text file, depending on its extension.").clicked() {
+            export_presets(&config.user_presets);
+        }
+    });
 }
 
 // Taken from https://stackoverflow.com/a/68174244
@@ -134,29 +403,88 @@ impl LoadingOptions {
             gen_opt: NumberGeneration::Random {
                 amount: 10,
                 disorder: DisorderSettings::default(),
+                seed: None,
             },
             source_opt: InstructionsSource::Executable {
                 path: config.push_swap_path.clone(),
                 mode: Default::default(),
+                strict: false,
             },
+            compare: false,
+            compare_opt: InstructionsSource::BuiltIn(Solver::default()),
             worker: None,
             gen_time: ExecutionTimeInfo::None,
             disorder: None,
             number_args: String::new(),
+            fixed_seed: false,
+            seed_text: String::new(),
+            last_seed: None,
+            preset_name: String::new(),
+            error_suite: None,
+            error_report: None,
         }
     }
 
-    async fn get_instructions_and_numbers(
+    fn error_report_ui(&mut self, ui: &mut Ui) {
+        if let Some(receiver) = self.error_suite.as_mut() {
+            if let Ok(report) = receiver.try_recv() {
+                self.error_report = Some(report);
+                self.error_suite = None;
+            } else {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Checking error management...");
+                });
+                return;
+            }
+        }
+        let Some(report) = &self.error_report else {
+            return;
+        };
+        let results = match report {
+            Ok(results) => results,
+            Err(err) => {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    format!("⚠ Failed to run the program: {}", err),
+                );
+                return;
+            }
+        };
+        let passed = results.iter().filter(|result| result.passed()).count();
+        ui.collapsing(
+            format!(
+                "Error management: {}/{} cases passed",
+                passed,
+                results.len()
+            ),
+            |ui| {
+                for result in results {
+                    let case = result.case;
+                    let (icon, color) = if result.passed() {
+                        ("✔", Color32::GREEN)
+                    } else {
+                        ("✖", ui.visuals().error_fg_color)
+                    };
+                    let mut hover = format!("Arguments: {}", case.command_line());
+                    for failure in &result.failures {
+                        let _ = write!(hover, "\n{}", failure);
+                    }
+                    ui.colored_label(color, format!("{} {}", icon, case.name))
+                        .on_hover_text(hover);
+                }
+            },
+        );
+    }
+
+    async fn get_instructions(
         token: CancellationToken,
-        gen_opt: NumberGeneration,
-        source_opt: InstructionsSource,
-    ) -> Result<(String, Vec<i64>), String> {
-        let (instructions, numbers) = match &source_opt {
-            InstructionsSource::Executable { path, mode } => {
+        source_opt: &InstructionsSource,
+        numbers: &[i64],
+    ) -> Result<String, String> {
+        let instructions = match source_opt {
+            InstructionsSource::Executable { path, mode, .. } => {
                 let path = path.as_ref().ok_or("No executable selected".to_string())?;
-                let numbers = gen_opt
-                    .get_numbers(token.clone())
-                    .map_err(|err| err.to_string())?;
                 let args: Vec<_> = numbers.iter().map(|n| n.to_string()).collect();
                 let mut cmd = Command::new(path);
                 if *mode != SortingStrategy::None {
@@ -184,48 +512,42 @@ impl LoadingOptions {
                     let _ = stdout.read_to_end(&mut output);
                     sleep(Duration::from_millis(10));
                 }
-                let instructions = String::from_utf8(output)
-                    .map_err(|err| format!("failed to convert byte array to string: {}", err))?;
-                (instructions, numbers)
+                String::from_utf8(output)
+                    .map_err(|err| format!("failed to convert byte array to string: {}", err))?
             }
             InstructionsSource::File(path) => {
                 let path = path.as_ref().ok_or("No file selected".to_string())?;
-                let numbers = gen_opt.get_numbers(token).map_err(|err| err.to_string())?;
-                let instructions = fs::read_to_string(path)
-                    .map_err(|err| format!("failed to read from file: {}", err))?;
-                (instructions, numbers)
+                fs::read_to_string(path)
+                    .map_err(|err| format!("failed to read from file: {}", err))?
             }
-            InstructionsSource::Manual(instructions) => (
-                instructions.clone(),
-                gen_opt.get_numbers(token).map_err(|err| err.to_string())?,
-            ),
+            InstructionsSource::Manual(instructions) => instructions.clone(),
+            InstructionsSource::BuiltIn(solver) => format_instructions(&solver.solve(numbers)),
         };
-        Ok((instructions, numbers))
+        Ok(instructions)
     }
 
     async fn load_sim(
-        sender: Sender<Result<PushSwapSim, String>>,
         token: CancellationToken,
-        gen_opt: NumberGeneration,
-        source_opt: InstructionsSource,
-    ) {
-        let (instructions, numbers) =
-            match Self::get_instructions_and_numbers(token, gen_opt, source_opt).await {
-                Ok(pair) => pair,
-                Err(s) => {
-                    rfd::AsyncMessageDialog::new()
-                        .set_level(rfd::MessageLevel::Error)
-                        .set_title("Information Error")
-                        .set_description(format!("An error occurred:\n{}", s))
-                        .set_buttons(rfd::MessageButtons::Ok)
-                        .show()
-                        .await;
-                    sender
-                        .send(Err(format!("Loading Error: {}", s)))
-                        .expect("failed to send message through channel");
-                    return;
-                }
-            };
+        source_opt: &InstructionsSource,
+        numbers: &[i64],
+    ) -> Result<PushSwapSim, String> {
+        let strict = matches!(
+            source_opt,
+            InstructionsSource::Executable { strict: true, .. }
+        );
+        let instructions = match Self::get_instructions(token, source_opt, numbers).await {
+            Ok(instructions) => instructions,
+            Err(s) => {
+                rfd::AsyncMessageDialog::new()
+                    .set_level(rfd::MessageLevel::Error)
+                    .set_title("Information Error")
+                    .set_description(format!("An error occurred:\n{}", s))
+                    .set_buttons(rfd::MessageButtons::Ok)
+                    .show()
+                    .await;
+                return Err(format!("Loading Error: {}", s));
+            }
+        };
         let number_args =
             numbers
                 .iter()
@@ -234,33 +556,107 @@ impl LoadingOptions {
                     let _ = write!(acc, "{} ", n);
                     acc
                 });
-        let mut sim = PushSwapSim::default();
-        match sim.load_random(&numbers, &instructions) {
-            Ok(_) => {
-                sender
-                    .send(Ok(sim))
-                    .expect("failed to send message through channel");
+        if strict {
+            let issues = check_strict_format(&instructions);
+            if !issues.is_empty() {
+                eprintln!("Program output does not follow the strict format!");
+                for issue in &issues {
+                    eprintln!("{}", issue);
+                }
+                let mut description = format!(
+                    "The program output has {} formatting issue(s):\n",
+                    issues.len()
+                );
+                for issue in issues.iter().take(MAX_REPORTED_ISSUES) {
+                    let _ = writeln!(description, "{}", issue);
+                }
+                if issues.len() > MAX_REPORTED_ISSUES {
+                    let _ = writeln!(
+                        description,
+                        "... and {} more (see terminal output)",
+                        issues.len() - MAX_REPORTED_ISSUES
+                    );
+                }
+                rfd::AsyncMessageDialog::new()
+                    .set_level(rfd::MessageLevel::Error)
+                    .set_title("Strict format error")
+                    .set_description(description)
+                    .set_buttons(rfd::MessageButtons::Ok)
+                    .show()
+                    .await;
+                return Err(format!(
+                    "Format Error: {} issue(s), first at {}",
+                    issues.len(),
+                    issues[0]
+                ));
             }
-            Err(line) => {
+        }
+        let mut sim = PushSwapSim::default();
+        match sim.load_random(numbers, &instructions) {
+            Ok(_) => Ok(sim),
+            Err(err) => {
                 eprintln!("Error while loading instructions!");
-                eprintln!("Failed at instruction {}", line);
+                eprintln!("Failed at instruction {}, {}", err.index, err);
+                eprintln!("{}", err.snippet());
                 eprintln!("Numbers: [{}]", number_args);
                 eprintln!("List of instructions: {}", instructions);
                 rfd::AsyncMessageDialog::new()
                     .set_level(rfd::MessageLevel::Error)
                     .set_title("Instruction parsing error")
                     .set_description(format!(
-                        "Instruction {} is not a valid push_swap instruction",
-                        line
+                        "Instruction {} is not a valid push_swap instruction.\nLine {}, column {}: `{}`\n\n{}",
+                        err.index,
+                        err.line,
+                        err.column,
+                        err.token,
+                        err.snippet()
                     ))
                     .set_buttons(rfd::MessageButtons::Ok)
                     .show()
                     .await;
+                Err(format!(
+                    "Parsing Error at line {}: `{}`",
+                    err.line, err.line_text
+                ))
+            }
+        }
+    }
+
+    async fn load_sims(
+        sender: Sender<Result<LoadedSims, String>>,
+        token: CancellationToken,
+        gen_opt: NumberGeneration,
+        source_opt: InstructionsSource,
+        compare_opt: Option<InstructionsSource>,
+    ) {
+        let numbers = match gen_opt.get_numbers(token.clone()) {
+            Ok(numbers) => numbers,
+            Err(err) => {
+                rfd::AsyncMessageDialog::new()
+                    .set_level(rfd::MessageLevel::Error)
+                    .set_title("Information Error")
+                    .set_description(format!("An error occurred:\n{}", err))
+                    .set_buttons(rfd::MessageButtons::Ok)
+                    .show()
+                    .await;
                 sender
-                    .send(Err(format!("Parsing Error at instruction {}", line)))
+                    .send(Err(format!("Loading Error: {}", err)))
                     .expect("failed to send message through channel");
+                return;
+            }
+        };
+        let mut res = Self::load_sim(token.clone(), &source_opt, &numbers)
+            .await
+            .map(|sim| (sim, None));
+        if let (Ok((_, comparison)), Some(compare_opt)) = (&mut res, compare_opt) {
+            match Self::load_sim(token, &compare_opt, &numbers).await {
+                Ok(sim) => *comparison = Some(sim),
+                Err(err) => res = Err(format!("Comparison {}", err)),
             }
         }
+        sender
+            .send(res)
+            .expect("failed to send message through channel");
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -270,33 +666,57 @@ impl LoadingOptions {
         config: &mut Config,
         open: &mut bool,
         sim: &mut PushSwapSim,
+        comparison: &mut Option<PushSwapSim>,
         regenerate_render_data: &mut bool,
-        projection: &mut cgmath::Matrix4<f32>,
         playing_sim: &mut bool,
         show_playback: &mut bool,
-    ) {
+    ) -> bool {
+        let mut sim_changed = false;
         Window::new("Loading Options").open(open).show(ctx, |ui| {
             ComboBox::from_label("Number Generation")
                 .selected_text(self.gen_opt.to_string())
                 .show_ui(ui, |ui| {
                     use NumberGeneration::*;
-                    let (range, num_gen, str, i, disorder) = match &self.gen_opt {
-                        Ordered(r) | ReverseOrdered(r) => (0..=(*r as i64 - 1), *r, String::new(), 0, DisorderSettings::default()),
-                        Random { amount, disorder } => (0..=(*amount as i64 - 1), *amount, String::new(), 0, disorder.clone()),
-                        RandomRanged { range, amount, disorder } => (range.clone(), *amount, String::new(), 0, disorder.clone()),
-                        Arbitrary(s) => (0..=9, 10, s.clone(), 0, DisorderSettings::default()),
-                        Preset(i) => (0..=9, 10, String::new(), *i, DisorderSettings::default()),
+                    let (range, num_gen, str, i, disorder, seed) = match &self.gen_opt {
+                        Ordered(r) | ReverseOrdered(r) => (0..=(*r as i64 - 1), *r, String::new(), 0, DisorderSettings::default(), None),
+                        Random { amount, disorder, seed } => (0..=(*amount as i64 - 1), *amount, String::new(), 0, disorder.clone(), *seed),
+                        RandomRanged { range, amount, disorder, seed } => (range.clone(), *amount, String::new(), 0, disorder.clone(), *seed),
+                        Arbitrary(s) => (0..=9, 10, s.clone(), 0, DisorderSettings::default(), None),
+                        Preset(i) => (0..=9, 10, String::new(), *i, DisorderSettings::default(), None),
+                        Pattern { amount, seed, .. } => (0..=(*amount as i64 - 1), *amount, String::new(), 0, DisorderSettings::default(), *seed),
+                        Saved(preset) => (0..=9, preset.numbers.len().max(1), String::new(), 0, DisorderSettings::default(), None),
+                        File(_) | Clipboard(_) => (0..=9, 10, String::new(), 0, DisorderSettings::default(), None),
+                    };
+                    let file = match &self.gen_opt {
+                        File(path) => path.clone(),
+                        _ => None,
+                    };
+                    let pasted = match &self.gen_opt {
+                        Clipboard(text) => text.clone(),
+                        _ => String::new(),
+                    };
+                    let saved = match &self.gen_opt {
+                        Saved(preset) => preset.clone(),
+                        _ => config.user_presets.first().cloned().unwrap_or_default(),
+                    };
+                    let pattern = match &self.gen_opt {
+                        Pattern { pattern, .. } => *pattern,
+                        _ => NumberPattern::ALL[0],
                     };
                     ui.selectable_value(&mut self.gen_opt, Ordered(num_gen), "Ordered").on_hover_text("Numbers will be generated in order from 0 to n.");
                     ui.selectable_value(&mut self.gen_opt, ReverseOrdered(num_gen), "Reverse Ordered").on_hover_text("Numbers will be generated in reverse order from n to 0.");
-                    ui.selectable_value(&mut self.gen_opt, Random { amount: num_gen, disorder: disorder.clone() }, "Random Normalized").on_hover_text("Numbers will be generated from 0 to n, then they will be shuffled.");
+                    ui.selectable_value(&mut self.gen_opt, Random { amount: num_gen, disorder: disorder.clone(), seed }, "Random Normalized").on_hover_text("Numbers will be generated from 0 to n, then they will be shuffled.");
                     ui.selectable_value(
                         &mut self.gen_opt,
-                        RandomRanged { range, amount: num_gen, disorder: disorder.clone() },
+                        RandomRanged { range, amount: num_gen, disorder: disorder.clone(), seed },
                         "Random from Custom Range",
                     ).on_hover_text("Numbers will be picked randomly from the specified range. Visually, the numbers will appear normalized.");
                     ui.selectable_value(&mut self.gen_opt, Arbitrary(str), "User Input").on_hover_text("You will be able to input a list of numbers yourself.");
+                    ui.selectable_value(&mut self.gen_opt, File(file), "From File").on_hover_text("Numbers will be read from the selected text file, e.g. a CI log or the output of a tester.");
+                    ui.selectable_value(&mut self.gen_opt, Clipboard(pasted), "From Clipboard").on_hover_text("Numbers will be pasted from the clipboard.");
                     ui.selectable_value(&mut self.gen_opt, Preset(i), "Preset").on_hover_text("Numbers will be selected from a few hardcoded presets. This option was added just for fun, but some of the tests in here are known to break some programs.");
+                    ui.selectable_value(&mut self.gen_opt, Pattern { pattern, amount: num_gen, seed }, "Pattern").on_hover_text("Numbers will follow one of a few shapes known to trip up sorting algorithms, with any amount of numbers.");
+                    ui.selectable_value(&mut self.gen_opt, Saved(saved), "Saved Preset").on_hover_text("Numbers will be taken from the presets you saved or imported.");
                 });
             match &mut self.gen_opt {
                 NumberGeneration::Ordered(r) | NumberGeneration::ReverseOrdered(r) => {
@@ -305,14 +725,15 @@ impl LoadingOptions {
                         ui.label("Numbers to Generate");
                     });
                 }
-                NumberGeneration::Random { amount, disorder } => {
+                NumberGeneration::Random { amount, disorder, seed } => {
                     ui.horizontal(|ui| {
                         DragValue::new(amount).ui(ui);
                         ui.label("Numbers to Generate");
                     });
-                    disorder.ui(ui, *amount);
+                    disorder_ui(disorder, ui, *amount);
+                    seed_ui(seed, &mut self.fixed_seed, &mut self.seed_text, ui);
                 }
-                NumberGeneration::RandomRanged { range, amount, disorder } => {
+                NumberGeneration::RandomRanged { range, amount, disorder, seed } => {
                     ui.horizontal(|ui| {
                         DragValue::new(amount).ui(ui);
                         ui.label("Numbers to Generate");
@@ -333,7 +754,8 @@ impl LoadingOptions {
                         *range = start..=end;
                         *amount = ((end - start + 1) as usize).min(*amount);
                     });
-                    disorder.ui(ui, *amount);
+                    disorder_ui(disorder, ui, *amount);
+                    seed_ui(seed, &mut self.fixed_seed, &mut self.seed_text, ui);
                 }
                 NumberGeneration::Arbitrary(s) => {
                     ui.horizontal(|ui| {
@@ -341,40 +763,10 @@ impl LoadingOptions {
                         ui.label("Numbers");
                     });
                 }
-                NumberGeneration::Preset(i) => {
-                    ComboBox::from_label("Number Preset")
-                        .selected_text(NUMBER_PRESETS[*i].0)
-                        .show_index(ui, i, NUMBER_PRESETS.len(), |i| {
-                            NUMBER_PRESETS[i].0
-                        });
-                }
-            };
-            ComboBox::from_label("Instructions Source")
-                .selected_text(self.source_opt.to_string())
-                .show_ui(ui, |ui| {
-                    use InstructionsSource::*;
-                    let (ins, file_path, exe_path) = match &self.source_opt {
-                        Manual(i) => (i.clone(), None, None),
-                        File(p) => (String::new(), p.clone(), None),
-                        Executable { path, .. } => (String::new(), None, path.clone()),
-                    };
-                    ui.selectable_value(&mut self.source_opt, Manual(ins), "User Input").on_hover_text("You will be able to input a list of push_swap instructions yourself.");
-                    ui.selectable_value(&mut self.source_opt, File(file_path), "From File").on_hover_text("The selected file's contents will be interpreted as a list of push_swap instructions.");
-                    ui.selectable_value(&mut self.source_opt, Executable { path: exe_path, mode: Default::default() }, "Program Output").on_hover_text("The selected program will be executed with the generated numbers above fed as input to the program. The output of the program will be interpreted as a list of push_swap instructions.");
-                });
-            match &mut self.source_opt {
-                InstructionsSource::Manual(i) => {
-                    ui.label("Type push_swap instructions below");
-                    ScrollArea::vertical().show(ui, |ui| {
-                        ui.add_sized([300., 5.], egui::TextEdit::multiline(i));
-                    });
-                }
-                InstructionsSource::File(p) => {
+                NumberGeneration::File(p) => {
                     ui.horizontal(|ui| {
                         if ui.button("Browse").clicked() {
-                            let path = rfd::FileDialog::new()
-                                .set_title("Select file")
-                                .pick_file();
+                            let path = rfd::FileDialog::new().set_title("Select numbers file").pick_file();
                             if let Some(path) = path {
                                 *p = Some(path);
                             }
@@ -385,34 +777,98 @@ impl LoadingOptions {
                             .unwrap_or("None".into());
                         ui.label(format!("Selected File: {}", path));
                     });
+                    ui.label("Numbers may be separated by spaces, newlines or commas, or written as ARG=\"...\".");
                 }
-                InstructionsSource::Executable { path, mode } => {
+                NumberGeneration::Clipboard(text) => {
                     ui.horizontal(|ui| {
-                        if ui.button("Browse").clicked() {
-                            let p = rfd::FileDialog::new()
-                                .set_title("Select push_swap executable")
-                                .pick_file();
-                            if let Some(p) = p {
-                                *path = Some(p.clone());
-                                config.push_swap_path = Some(p);
-                                config.save()
+                        if ui.button("Paste from clipboard").clicked() {
+                            match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+                                Ok(pasted) => *text = pasted,
+                                Err(err) => error_dialog("Clipboard Error", format!("Failed to paste from clipboard: {}", err)),
                             }
                         }
-                        let path = path
-                            .clone()
-                            .map(|p| p.to_string_lossy().to_string())
-                            .unwrap_or("None".into());
-                        ui.label(format!("Selected Program: {}", path));
+                        if let Ok(numbers) = parse_numbers_text(text) {
+                            ui.label(format!("{} numbers", numbers.len()));
+                        }
+                    });
+                    ui.label("Numbers may be separated by spaces, newlines or commas, or written as ARG=\"...\".");
+                    ScrollArea::vertical().max_height(100.).show(ui, |ui| {
+                        ui.add_sized([300., 5.], egui::TextEdit::multiline(text));
                     });
-                    ComboBox::from_label("Sorting Strategy")
-                        .selected_text(mode.to_string())
+                }
+                NumberGeneration::Preset(i) => {
+                    ComboBox::from_label("Number Preset")
+                        .selected_text(NUMBER_PRESETS[*i].0)
+                        .show_index(ui, i, NUMBER_PRESETS.len(), |i| {
+                            NUMBER_PRESETS[i].0
+                        });
+                }
+                NumberGeneration::Pattern { pattern, amount, seed } => {
+                    ComboBox::from_label("Number Pattern")
+                        .selected_text(pattern.to_string())
                         .show_ui(ui, |ui| {
-                            for m in SortingStrategy::ALL {
-                                ui.selectable_value(mode, m, m.to_string());
+                            for p in NumberPattern::ALL {
+                                let selected = std::mem::discriminant(pattern) == std::mem::discriminant(&p);
+                                if ui.selectable_label(selected, p.to_string()).clicked() && !selected {
+                                    *pattern = p;
+                                }
                             }
                         });
+                    ui.horizontal(|ui| {
+                        DragValue::new(amount).ui(ui);
+                        ui.label("Numbers to Generate");
+                    });
+                    if let Some((name, value)) = pattern.parameter_mut() {
+                        ui.horizontal(|ui| {
+                            DragValue::new(value).ui(ui);
+                            ui.label(name);
+                        });
+                    }
+                    seed_ui(seed, &mut self.fixed_seed, &mut self.seed_text, ui);
                 }
+                NumberGeneration::Saved(preset) => saved_preset_ui(ui, preset, config),
             };
+            let input_error = match &self.gen_opt {
+                NumberGeneration::Arbitrary(s) => parse_numbers(s).err(),
+                NumberGeneration::Clipboard(text) => parse_numbers_text(text).err(),
+                NumberGeneration::Saved(preset) => check_numbers(&preset.numbers).err(),
+                _ => None,
+            };
+            if let Some(err) = &input_error {
+                ui.colored_label(ui.visuals().error_fg_color, format!("⚠ Invalid numbers: {}", err));
+            }
+            let mut check_errors = source_ui(ui, &mut self.source_opt, config);
+            self.error_report_ui(ui);
+            ui.checkbox(&mut self.compare, "Compare with another instructions source").on_hover_text("Instructions from a second source will be run on the exact same numbers and shown next to the first ones, with synchronized playback.");
+            if self.compare {
+                ui.push_id("comparison", |ui| {
+                    ui.group(|ui| {
+                        if let Some(path) = source_ui(ui, &mut self.compare_opt, config) {
+                            check_errors = Some(path);
+                        }
+                    });
+                });
+            }
+            if let Some(path) = check_errors {
+                let (sender, receiver) = channel();
+                self.error_suite = Some(receiver);
+                self.error_report = None;
+                std::thread::spawn(move || {
+                    let _ = sender.send(run_suite(&path, CASE_TIMEOUT).map_err(|err| err.to_string()));
+                });
+            }
+            ui.collapsing("Advanced Settings", |ui| {
+                ui.horizontal(|ui| {
+                    let drag = DragValue::new(&mut config.keyframe_memory_mib)
+                        .range(1..=1024)
+                        .suffix(" MiB")
+                        .ui(ui);
+                    if drag.drag_stopped() || drag.lost_focus() {
+                        config.save();
+                    }
+                    ui.label("Seeking memory").on_hover_text("Maximum amount of memory used to store snapshots of the stacks, which make skipping through long lists of instructions faster. Applied on the next load.");
+                });
+            });
             ui.separator();
             let mut clear_worker = false;
             if let Some(worker) = self.worker.as_mut() && let Ok(res) = worker.receiver.try_recv() {
@@ -420,9 +876,22 @@ impl LoadingOptions {
                 let duration = now - worker.start_time;
                 clear_worker = true;
                 match res {
-                    Ok(res) => {
+                    Ok((res, compared)) => {
                         *sim = res;
+                        *comparison = compared;
+                        sim.set_keyframe_memory(config.keyframe_memory_mib * 1024 * 1024);
+                        if let Some(comparison) = comparison {
+                            comparison.set_keyframe_memory(config.keyframe_memory_mib * 1024 * 1024);
+                        }
                         self.disorder = Some(compute_disorder(sim.stack_a()));
+                        self.number_args = sim
+                            .initial_numbers()
+                            .iter()
+                            .filter_map(|n| sim.original_value(*n))
+                            .map(|n| n.to_string())
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        self.last_seed = worker.seed;
                         self.gen_time = if worker.token.is_cancelled() {
                             ExecutionTimeInfo::Killed(duration)
                         } else {
@@ -430,7 +899,7 @@ impl LoadingOptions {
                         };
                         *regenerate_render_data = true;
                         *show_playback = true;
-                        update_projection(projection, sim.amount() as f32);
+                        sim_changed = true;
                     }
                     Err(e) => {
                         self.gen_time = ExecutionTimeInfo::Error(e);
@@ -443,16 +912,19 @@ impl LoadingOptions {
             ui.horizontal(|ui| {
                 match self.worker.as_ref() {
                     None => {
-                        if ui.button("Visualize").clicked() {
+                        let visualize = ui.add_enabled(input_error.is_none(), egui::Button::new("Visualize")).on_disabled_hover_text("The numbers would be rejected by push_swap.");
+                        if visualize.clicked() {
                             let (sender, receiver) = channel();
                             let token = CancellationToken::new();
                             let token_clone = token.clone();
-                            let gen_clone = self.gen_opt.clone();
+                            let mut gen_clone = self.gen_opt.clone();
+                            let seed = gen_clone.resolve_seed();
                             let source_clone = self.source_opt.clone();
+                            let compare_clone = self.compare.then(|| self.compare_opt.clone());
                             let start_time = Instant::now();
-                            self.worker = Some(AsyncWorker { receiver, token, start_time });
+                            self.worker = Some(AsyncWorker { receiver, token, start_time, seed });
                             tokio::spawn(async move {
-                                Self::load_sim(sender, token_clone, gen_clone, source_clone).await;
+                                Self::load_sims(sender, token_clone, gen_clone, source_clone, compare_clone).await;
                             });
                         }
                     }
@@ -465,12 +937,15 @@ impl LoadingOptions {
                 }
                 if ui.button("Clear").clicked() {
                     sim.clear();
+                    *comparison = None;
                     self.number_args.clear();
                     *regenerate_render_data = true;
                     *playing_sim = false;
                     *show_playback = false;
                     self.gen_time = ExecutionTimeInfo::None;
                     self.disorder = None;
+                    self.last_seed = None;
+                    sim_changed = true;
                 }
                 if ui.button("Copy numbers to clipboard").on_hover_text("The list of generated numbers will be collapsed into a single line that can be pasted as program arguments. Useful if you want to debug a random sequence that was just generated.").clicked() {
                     let copy = self.number_args.clone();
@@ -506,6 +981,32 @@ impl LoadingOptions {
             if let Some(dis) = self.disorder {
                 ui.label(format!("Disorder: {:.2}%", dis * 100.));
             }
+            if let Some(seed) = self.last_seed {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Seed: {}", seed));
+                    if ui.small_button("Copy").clicked() {
+                        ui.ctx().copy_text(seed.to_string());
+                    }
+                    if ui.small_button("Reuse").on_hover_text("Generates the same numbers on the next Visualize.").clicked() {
+                        self.fixed_seed = true;
+                        self.seed_text = seed.to_string();
+                    }
+                });
+            }
+            if !self.number_args.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.preset_name);
+                    let name = self.preset_name.trim();
+                    let save = ui.add_enabled(!name.is_empty(), egui::Button::new("Save as preset")).on_hover_text("Saves the current numbers under this name, replacing any preset with the same name.");
+                    if save.clicked() {
+                        let numbers = sim.initial_numbers().iter().filter_map(|n| sim.original_value(*n)).collect();
+                        merge_presets(&mut config.user_presets, vec![UserPreset { name: name.to_string(), numbers }]);
+                        config.save();
+                        self.preset_name.clear();
+                    }
+                });
+            }
         });
+        sim_changed
     }
 }
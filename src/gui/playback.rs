@@ -1,30 +1,533 @@
-use std::{cmp::Ordering, time::Duration};
+use std::{cmp::Ordering, fmt::Display, fs::File, io::BufWriter, time::Duration};
 
-use egui::{Align, Button, DragValue, Layout, Sense, Ui, Widget, Window};
+use egui::{
+    Align, Button, Color32, ComboBox, DragValue, Grid, Layout, Pos2, RichText, Sense, Shape,
+    Stroke, Ui, Widget, Window,
+};
+use tokio::sync::oneshot::{Receiver, channel, error::TryRecvError};
 
-use crate::sim::PushSwapSim;
+use psv::{
+    bounds::{LowerBounds, efficiency},
+    breakpoints::Breakpoint,
+    optimizer::{Optimization, optimize},
+    oracle::{MAX_ORACLE_NUMBERS, optimal_solution},
+    parser::parse_push_swap,
+    progress::{ProgressStep, progress},
+    sim::{PushSwapInstruction, PushSwapSim},
+    stats::{InstructionStats, NumberMoves, number_moves},
+    trace::{TraceFormat, write_trace},
+};
 
 pub struct PlaybackControls {
     auto_scroll_table: bool,
     force_scroll: bool,
+    original: Vec<PushSwapInstruction>,
+    optimization: Optimization,
+    show_optimized: bool,
+    no_ops: usize,
+    stats: InstructionStats,
+    optimal: Option<Vec<PushSwapInstruction>>,
+    /// Receives the optimal solution while it is being searched for.
+    optimal_receiver: Option<Receiver<Option<Vec<PushSwapInstruction>>>>,
+    lower_bounds: LowerBounds,
+    progress: Vec<ProgressStep>,
+    breakpoints: Vec<(bool, Breakpoint)>,
+    new_breakpoint: BreakpointKind,
+    new_counter: usize,
+    new_number: u32,
+    new_pattern: String,
+    last_hit: Option<String>,
+    sync_mode: SyncMode,
+    tracked: Option<(u32, Vec<NumberMoves>)>,
 }
 
+/// How the compared run follows the main run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SyncMode {
+    Completion,
+    Step,
+}
+
+impl SyncMode {
+    const ALL: [SyncMode; 2] = [SyncMode::Completion, SyncMode::Step];
+}
+
+impl Display for SyncMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            SyncMode::Completion => "Percentage of completion",
+            SyncMode::Step => "Absolute step",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BreakpointKind {
+    ProgramCounter,
+    StackBEmpty,
+    PushedToB,
+    StackASorted,
+    Pattern,
+}
+
+impl BreakpointKind {
+    const ALL: [BreakpointKind; 5] = [
+        BreakpointKind::ProgramCounter,
+        BreakpointKind::StackBEmpty,
+        BreakpointKind::PushedToB,
+        BreakpointKind::StackASorted,
+        BreakpointKind::Pattern,
+    ];
+}
+
+impl Display for BreakpointKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            BreakpointKind::ProgramCounter => "Program counter",
+            BreakpointKind::StackBEmpty => "Stack B becomes empty",
+            BreakpointKind::PushedToB => "Number pushed to stack B",
+            BreakpointKind::StackASorted => "Stack A becomes sorted",
+            BreakpointKind::Pattern => "Instruction pattern",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+const DISORDER_COLOR: Color32 = Color32::from_rgb(230, 80, 80);
+const SORTED_PREFIX_COLOR: Color32 = Color32::from_rgb(80, 200, 120);
+const STACK_B_COLOR: Color32 = Color32::from_rgb(90, 150, 240);
+
 impl Default for PlaybackControls {
     fn default() -> Self {
         Self {
             auto_scroll_table: true,
             force_scroll: false,
+            original: vec![],
+            optimization: Optimization::default(),
+            show_optimized: false,
+            no_ops: 0,
+            stats: InstructionStats::default(),
+            optimal: None,
+            optimal_receiver: None,
+            lower_bounds: LowerBounds::default(),
+            progress: vec![],
+            breakpoints: vec![],
+            new_breakpoint: BreakpointKind::ProgramCounter,
+            new_counter: 0,
+            new_number: 0,
+            new_pattern: String::new(),
+            last_hit: None,
+            sync_mode: SyncMode::Completion,
+            tracked: None,
         }
     }
 }
 
 impl PlaybackControls {
+    pub fn sim_changed(&mut self, sim: &PushSwapSim) {
+        self.original = sim.instructions().to_vec();
+        self.optimization = optimize(sim.initial_numbers(), sim.instructions());
+        self.show_optimized = false;
+        self.no_ops = sim.no_ops().len();
+        self.stats = InstructionStats::new(sim.instructions(), sim.amount());
+        self.optimal = None;
+        self.optimal_receiver = None;
+        let numbers = sim.initial_numbers().to_vec();
+        if (1..=MAX_ORACLE_NUMBERS).contains(&numbers.len()) {
+            // The search can take a while, so it runs outside of the frame
+            let (sender, receiver) = channel();
+            self.optimal_receiver = Some(receiver);
+            tokio::task::spawn_blocking(move || {
+                let _ = sender.send(optimal_solution(&numbers));
+            });
+        }
+        self.lower_bounds = LowerBounds::new(sim.initial_numbers());
+        self.progress = progress(sim);
+        self.last_hit = None;
+        let tracked = self.tracked().filter(|n| (*n as usize) < sim.amount());
+        self.track(sim, tracked);
+    }
+
+    /// Selects the number to follow through the run, if any.
+    pub fn track(&mut self, sim: &PushSwapSim, number: Option<u32>) {
+        self.tracked = number.map(|n| (n, number_moves(sim, n)));
+    }
+
+    /// The number followed through the run, if any.
+    pub fn tracked(&self) -> Option<u32> {
+        self.tracked.as_ref().map(|(n, _)| *n)
+    }
+
+    /// Moves of the followed number so far, and over the whole run.
+    pub fn tracked_moves(&self, sim: &PushSwapSim) -> Option<(NumberMoves, NumberMoves)> {
+        let (_, moves) = self.tracked.as_ref()?;
+        Some((*moves.get(sim.program_counter())?, *moves.last()?))
+    }
+
+    /// Checks whether the instruction that was just executed hits an enabled breakpoint.
+    pub fn breakpoint_hit(&mut self, sim: &PushSwapSim) -> bool {
+        let hit = self
+            .breakpoints
+            .iter()
+            .find(|(enabled, breakpoint)| *enabled && breakpoint.is_hit(sim, &self.progress));
+        if let Some((_, breakpoint)) = hit {
+            self.last_hit = Some(format!(
+                "Paused at instruction {}: {}",
+                sim.program_counter(),
+                breakpoint
+            ));
+        }
+        hit.is_some()
+    }
+
+    /// Moves the compared run to the position matching the main run. Returns whether it moved.
+    pub fn sync_comparison(&self, sim: &PushSwapSim, comparison: &mut PushSwapSim) -> bool {
+        let len = comparison.instructions().len();
+        let counter = match self.sync_mode {
+            SyncMode::Completion if sim.instructions().is_empty() => len,
+            SyncMode::Completion => {
+                let t = sim.program_counter() as f64 / sim.instructions().len() as f64;
+                (t * len as f64).round() as usize
+            }
+            SyncMode::Step => sim.program_counter().min(len),
+        };
+        comparison.skip_to(counter)
+    }
+
+    fn comparison_ui(
+        &mut self,
+        ui: &mut Ui,
+        sim: &mut PushSwapSim,
+        comparison: &mut Option<PushSwapSim>,
+        regenerate_render_data: &mut bool,
+    ) {
+        let Some(compared) = comparison.as_mut() else {
+            return;
+        };
+        let len = compared.instructions().len();
+        ui.label(format!(
+            "Compared run (right): {} instructions ({:+} compared to the main run)",
+            len,
+            len as i64 - sim.instructions().len() as i64
+        ));
+        ui.label(format!(
+            "Compared Program Counter: {}",
+            compared.program_counter()
+        ));
+        ComboBox::from_label("Synchronize by")
+            .selected_text(self.sync_mode.to_string())
+            .show_ui(ui, |ui| {
+                for mode in SyncMode::ALL {
+                    ui.selectable_value(&mut self.sync_mode, mode, mode.to_string());
+                }
+            });
+        if ui
+            .button("Swap runs")
+            .on_hover_text("The compared run becomes the main run, shown on the left and driven by these controls.")
+            .clicked()
+        {
+            std::mem::swap(sim, compared);
+            self.sim_changed(sim);
+            *regenerate_render_data = true;
+        }
+    }
+
+    fn breakpoints_ui(&mut self, ui: &mut Ui, sim: &PushSwapSim) {
+        if let Some(hit) = &self.last_hit {
+            ui.label(RichText::new(hit).color(ui.visuals().warn_fg_color));
+        }
+        let mut removed = None;
+        for (i, (enabled, breakpoint)) in self.breakpoints.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                let checkbox = ui.checkbox(enabled, breakpoint.to_string());
+                if let Breakpoint::PushedToB(n) = breakpoint
+                    && let Some(original) = sim.original_value(*n)
+                {
+                    checkbox.on_hover_text(format!("Original value: {}", original));
+                }
+                if ui.small_button("Remove").clicked() {
+                    removed = Some(i);
+                }
+            });
+        }
+        if let Some(i) = removed {
+            self.breakpoints.remove(i);
+        }
+        ComboBox::from_label("Breakpoint condition")
+            .selected_text(self.new_breakpoint.to_string())
+            .show_ui(ui, |ui| {
+                for kind in BreakpointKind::ALL {
+                    ui.selectable_value(&mut self.new_breakpoint, kind, kind.to_string());
+                }
+            });
+        ui.horizontal(|ui| {
+            let breakpoint = match self.new_breakpoint {
+                BreakpointKind::ProgramCounter => {
+                    DragValue::new(&mut self.new_counter)
+                        .range(1..=sim.instructions().len().max(1))
+                        .ui(ui);
+                    Some(Breakpoint::ProgramCounter(self.new_counter))
+                }
+                BreakpointKind::StackBEmpty => Some(Breakpoint::StackBEmpty),
+                BreakpointKind::PushedToB => {
+                    DragValue::new(&mut self.new_number)
+                        .range(0..=sim.initial_numbers().len().saturating_sub(1))
+                        .ui(ui)
+                        .on_hover_text("Normalized number, from 0 to the amount of numbers - 1");
+                    if let Some(original) = sim.original_value(self.new_number) {
+                        ui.label(format!("(original value: {})", original));
+                    }
+                    Some(Breakpoint::PushedToB(self.new_number))
+                }
+                BreakpointKind::StackASorted => Some(Breakpoint::StackASorted),
+                BreakpointKind::Pattern => {
+                    ui.text_edit_singleline(&mut self.new_pattern)
+                        .on_hover_text("Whitespace-separated instructions, e.g. pb pb ra");
+                    parse_push_swap(&self.new_pattern)
+                        .ok()
+                        .filter(|pattern| !pattern.is_empty())
+                        .map(Breakpoint::Pattern)
+                }
+            };
+            if ui
+                .add_enabled(breakpoint.is_some(), Button::new("Add breakpoint"))
+                .clicked()
+                && let Some(breakpoint) = breakpoint
+            {
+                self.breakpoints.push((true, breakpoint));
+            }
+        });
+    }
+
+    fn instruction_count_ui(&self, ui: &mut Ui, sim: &PushSwapSim) {
+        let count = sim.instructions().len();
+        if sim.initial_numbers().is_empty() {
+            ui.label(format!("Instructions loaded: {}", count));
+            return;
+        }
+        let (bound, hint) = match &self.optimal {
+            Some(optimal) => (
+                optimal.len(),
+                format!("Optimal: {} instructions", optimal.len()),
+            ),
+            None => (
+                self.lower_bounds.best(),
+                format!(
+                    "Lower bound: {} instructions\n\
+                    At least {} instructions to move the numbers outside of the longest increasing sequence\n\
+                    At least {} rotations if the numbers are only rotated",
+                    self.lower_bounds.best(),
+                    self.lower_bounds.moved,
+                    self.lower_bounds.rotation
+                ),
+            ),
+        };
+        ui.label(format!(
+            "Instructions loaded: {} (efficiency: {:.2}%)",
+            count,
+            efficiency(bound, count) * 100.
+        ))
+        .on_hover_text(hint);
+    }
+
+    fn optimization_ui(
+        &mut self,
+        ui: &mut Ui,
+        sim: &mut PushSwapSim,
+        play_sim: &mut bool,
+        regenerate_render_data: &mut bool,
+    ) {
+        if let Some(receiver) = &mut self.optimal_receiver {
+            match receiver.try_recv() {
+                Ok(optimal) => {
+                    self.optimal = optimal;
+                    self.optimal_receiver = None;
+                }
+                Err(TryRecvError::Empty) => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Searching for the optimal solution");
+                    });
+                }
+                Err(TryRecvError::Closed) => self.optimal_receiver = None,
+            }
+        }
+        if let Some(optimal) = &self.optimal {
+            let optimal_text = optimal
+                .iter()
+                .map(|ins| ins.to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+            ui.label(format!(
+                "Your program: {} instructions, optimal: {}",
+                self.original.len(),
+                optimal.len()
+            ))
+            .on_hover_text(format!("One of the optimal solutions: {}", optimal_text));
+        }
+        if self.no_ops > 0 {
+            ui.label(format!("{} instructions have no effect", self.no_ops))
+                .on_hover_text("These instructions did not change the stacks, e.g. pa with an empty stack B. They are highlighted in the instruction flow.");
+        }
+        let saved = self.optimization.saved();
+        if saved == 0 {
+            return;
+        }
+        ui.label(format!("{} instructions could have been saved", saved))
+            .on_hover_text("Adjacent instructions that could have been merged (e.g. ra rb into rr) or that cancel each other out (e.g. pb pa) are highlighted in the instruction flow.");
+        if ui
+            .checkbox(&mut self.show_optimized, "Visualize optimized instructions")
+            .changed()
+        {
+            let instructions = if self.show_optimized {
+                self.optimization.instructions.clone()
+            } else {
+                self.original.clone()
+            };
+            sim.replace_instructions(instructions);
+            self.no_ops = sim.no_ops().len();
+            self.stats = InstructionStats::new(sim.instructions(), sim.amount());
+            self.progress = progress(sim);
+            self.track(sim, self.tracked());
+            *play_sim = false;
+            *regenerate_render_data = true;
+        }
+    }
+
+    fn timeline_ui(&self, ui: &mut Ui, sim: &mut PushSwapSim, regenerate_render_data: &mut bool) {
+        if self.progress.len() < 2 {
+            ui.label("No instructions loaded");
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Disorder of stack A").color(DISORDER_COLOR));
+            ui.label(RichText::new("Sorted prefix of stack A").color(SORTED_PREFIX_COLOR));
+            ui.label(RichText::new("Size of stack B").color(STACK_B_COLOR));
+        });
+        let amount = sim.initial_numbers().len().max(1) as f32;
+        let last = self.progress.len() - 1;
+        let size = egui::vec2(ui.available_width(), 120.);
+        let (response, painter) = ui.allocate_painter(size, Sense::click_and_drag());
+        let rect = response.rect;
+        painter.rect_filled(rect, 0., ui.visuals().extreme_bg_color);
+        let x_of = |pc: usize| rect.left() + rect.width() * pc as f32 / last as f32;
+        let pc_at = |x: f32| {
+            (((x - rect.left()) / rect.width()).clamp(0., 1.) * last as f32).round() as usize
+        };
+        // Only one point per pixel column is drawn, no matter how many instructions there are
+        let columns = (rect.width() as usize).clamp(1, last);
+        let curves = [DISORDER_COLOR, SORTED_PREFIX_COLOR, STACK_B_COLOR];
+        for (curve, color) in curves.into_iter().enumerate() {
+            let points: Vec<_> = (0..=columns)
+                .map(|column| {
+                    let pc = column * last / columns;
+                    let step = &self.progress[pc];
+                    let value = match curve {
+                        0 => step.disorder as f32,
+                        1 => step.sorted_prefix as f32 / amount,
+                        _ => step.size_b as f32 / amount,
+                    };
+                    let y = rect.bottom() - rect.height() * value;
+                    Pos2::new(x_of(pc), y)
+                })
+                .collect();
+            painter.add(Shape::line(points, Stroke::new(1.5, color)));
+        }
+        let current = x_of(sim.program_counter());
+        painter.vline(
+            current,
+            rect.y_range(),
+            ui.visuals().widgets.active.fg_stroke,
+        );
+        if let Some(pos) = response.interact_pointer_pos()
+            && (response.clicked() || response.dragged())
+        {
+            sim.skip_to(pc_at(pos.x));
+            *regenerate_render_data = true;
+        }
+        let pc = response
+            .hover_pos()
+            .map_or(sim.program_counter(), |pos| pc_at(pos.x));
+        let step = &self.progress[pc];
+        ui.label(format!(
+            "Step {}: disorder of stack A {:.2}%, sorted prefix: {}, stack A: {}, stack B: {}",
+            pc,
+            step.disorder * 100.,
+            step.sorted_prefix,
+            step.size_a,
+            step.size_b
+        ));
+    }
+
+    fn export_trace_ui(&self, ui: &mut Ui, sim: &PushSwapSim) {
+        let export = ui
+            .add_enabled(
+                !sim.initial_numbers().is_empty(),
+                Button::new("Export step trace"),
+            )
+            .on_hover_text(
+                "Save the state of both stacks after every instruction as JSON Lines or CSV.",
+            );
+        if !export.clicked() {
+            return;
+        }
+        let mut dialog = rfd::FileDialog::new()
+            .set_title("Export step trace")
+            .set_file_name(format!("trace.{}", TraceFormat::default().extension()));
+        for format in TraceFormat::ALL {
+            dialog = dialog.add_filter(format.to_string(), &[format.extension()]);
+        }
+        let Some(path) = dialog.save_file() else {
+            return;
+        };
+        let format = TraceFormat::from_path(&path);
+        let res =
+            File::create(&path).and_then(|file| write_trace(sim, format, BufWriter::new(file)));
+        if let Err(err) = res {
+            rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Error)
+                .set_title("Export Error")
+                .set_description(format!("Failed to export step trace: {}", err))
+                .set_buttons(rfd::MessageButtons::Ok)
+                .show();
+        }
+    }
+
+    fn stats_ui(&self, ui: &mut Ui) {
+        let stats = &self.stats;
+        Grid::new("instruction_stats").striped(true).show(ui, |ui| {
+            ui.strong("Instruction");
+            ui.strong("Count");
+            ui.strong("Share");
+            ui.end_row();
+            for ins in PushSwapInstruction::ALL {
+                let count = stats.count(ins);
+                ui.label(ins.to_string());
+                ui.label(count.to_string());
+                ui.label(format!("{:.2}%", stats.share(count) * 100.));
+                ui.end_row();
+            }
+        });
+        ui.label(format!(
+            "Combined instructions (ss, rr, rrr): {:.2}%",
+            stats.share(stats.combined()) * 100.
+        ));
+        ui.label(format!("Push pairs: {}", stats.push_pairs))
+            .on_hover_text("Amount of numbers pushed to stack B and back to stack A.");
+        match stats.longest_rotation {
+            Some((ins, len)) => ui.label(format!("Longest rotation run: {} x {}", len, ins)),
+            None => ui.label("Longest rotation run: N/A"),
+        };
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn ui(
         &mut self,
         ctx: &egui::Context,
         open: &mut bool,
         sim: &mut PushSwapSim,
+        comparison: &mut Option<PushSwapSim>,
         play_sim: &mut bool,
         temp_stop_sim: &mut bool,
         exec_duration: &mut Duration,
@@ -34,8 +537,14 @@ impl PlaybackControls {
         Window::new("Playback Controls")
             .open(open)
             .show(ctx, move |ui| {
-                ui.label(format!("Instructions loaded: {}", sim.instructions().len()));
+                self.instruction_count_ui(ui, sim);
                 ui.label(format!("Program Counter: {}", sim.program_counter()));
+                self.optimization_ui(ui, sim, play_sim, regenerate_render_data);
+                if comparison.is_some() {
+                    ui.collapsing("Comparison", |ui| {
+                        self.comparison_ui(ui, sim, comparison, regenerate_render_data);
+                    });
+                }
                 ui.scope(|ui| {
                     let instructions = sim.instructions();
                     ui.style_mut().spacing.slider_width = ui.available_width();
@@ -148,7 +657,19 @@ impl PlaybackControls {
                     ui.label("instructions per second");
                     *exec_duration = Duration::from_secs_f64(1. / exec_rate as f64);
                 });
+                self.export_trace_ui(ui, sim);
                 ui.separator();
+                ui.collapsing("Instruction statistics", |ui| {
+                    self.stats_ui(ui);
+                });
+                ui.collapsing("Breakpoints", |ui| {
+                    self.breakpoints_ui(ui, sim);
+                });
+                egui::CollapsingHeader::new("Progress timeline")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        self.timeline_ui(ui, sim, regenerate_render_data);
+                    });
                 ui.collapsing("push_swap instruction flow", |ui| {
                     self.instructions_table_ui(ui, sim, *play_sim, regenerate_render_data);
                 });
@@ -240,6 +761,16 @@ impl PlaybackControls {
                     row.col(|ui| {
                         if reached_end {
                             ui.label("End of program");
+                        } else if sim.is_no_op(row_index) {
+                            let text = RichText::new(sim.instructions()[row_index].to_string())
+                                .color(ui.visuals().error_fg_color);
+                            ui.label(text)
+                                .on_hover_text("This instruction has no effect");
+                        } else if !self.show_optimized && self.optimization.rewritten[row_index] {
+                            let text = RichText::new(sim.instructions()[row_index].to_string())
+                                .color(ui.visuals().warn_fg_color);
+                            ui.label(text)
+                                .on_hover_text("This instruction can be merged or cancelled out");
                         } else {
                             ui.label(sim.instructions()[row_index].to_string());
                         }
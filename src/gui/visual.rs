@@ -12,6 +12,7 @@ pub struct VisualOptions {
     color_profile: ColorProfile,
     opacity: u8,
     preview_nums: Vec<u8>,
+    proportional_bars: bool,
 }
 
 impl VisualOptions {
@@ -26,6 +27,7 @@ impl VisualOptions {
             color_profile,
             opacity: config.egui_opacity,
             preview_nums,
+            proportional_bars: false,
         }
     }
 
@@ -37,6 +39,10 @@ impl VisualOptions {
         self.opacity
     }
 
+    pub fn proportional_bars(&self) -> bool {
+        self.proportional_bars
+    }
+
     fn default_subdivisions() -> Vec<[f32; 3]> {
         vec![
             [1.0, 0.0, 0.0],
@@ -187,6 +193,7 @@ impl VisualOptions {
                     config.save();
                 }
             });
+            ui.checkbox(&mut self.proportional_bars, "Bar length proportional to original values").on_hover_text("Bars are drawn proportionally to the numbers before normalization instead of their rank, negative numbers extending to the left. Useful to spot gaps and extreme values in custom ranges.");
             ui.separator();
             let can_switch_or_delete = config.color_profiles.len() > 1;
             ui.add_enabled_ui(can_switch_or_delete, |ui| {
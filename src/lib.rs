@@ -0,0 +1,33 @@
+//! Simulator, parser and number generators behind psv, the push_swap visualizer.
+//!
+//! The GUI and the benchmark are built on top of this library, which can also be used to
+//! write custom test harnesses for push_swap programs:
+//!
+//! ```
+//! use psv::{numbers::NumberGeneration, sim::PushSwapSim};
+//! use tokio_util::sync::CancellationToken;
+//!
+//! let numbers = NumberGeneration::ReverseOrdered(3)
+//!     .get_numbers(CancellationToken::new())
+//!     .unwrap();
+//! let mut sim = PushSwapSim::default();
+//! sim.load_random(&numbers, "sa\nrra\n").unwrap();
+//! while sim.step() {}
+//! assert!(sim.is_sorted());
+//! ```
+#![warn(missing_docs)]
+
+pub mod bounds;
+pub mod breakpoints;
+pub mod conformance;
+mod fenwick;
+pub mod numbers;
+pub mod optimizer;
+pub mod oracle;
+pub mod parser;
+pub mod presets;
+pub mod progress;
+pub mod sim;
+pub mod solvers;
+pub mod stats;
+pub mod trace;
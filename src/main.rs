@@ -2,6 +2,9 @@ use std::{env::args, process::exit};
 
 use app::App;
 use bench::benchmark;
+use check::check;
+use errors::check_errors;
+use export::export_trace;
 use winit::{
     error::EventLoopError,
     event_loop::{ControlFlow, EventLoop},
@@ -9,13 +12,14 @@ use winit::{
 
 mod app;
 mod bench;
+mod check;
 mod config;
 mod egui_tools;
+mod errors;
+mod export;
 mod gpu;
 mod gradient;
 mod gui;
-mod numbers;
-mod sim;
 mod sortview;
 mod util;
 mod vertex;
@@ -27,6 +31,15 @@ async fn main() -> Result<(), EventLoopError> {
         benchmark();
         exit(0);
     }
+    if args.len() > 1 && args[1] == "check" {
+        exit(check(&args[2..]));
+    }
+    if args.len() > 1 && args[1] == "errors" {
+        exit(check_errors(&args[2..]));
+    }
+    if args.len() > 1 && args[1] == "trace" {
+        exit(export_trace(&args[2..]));
+    }
 
     let event_loop = EventLoop::new().expect("failed to create event loop");
     event_loop.set_control_flow(ControlFlow::Poll);
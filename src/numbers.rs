@@ -1,15 +1,43 @@
-use std::{collections::HashSet, fmt::Display, num::ParseIntError, ops::RangeInclusive};
+//! Generation of the numbers to sort.
 
-use egui::{Color32, DragValue, Ui, Widget};
-use egui_double_slider::DoubleSlider;
-use rand::seq::SliceRandom;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    fs, io,
+    ops::RangeInclusive,
+    path::PathBuf,
+};
+
+use rand::{Rng, RngExt, SeedableRng, rngs::ChaCha8Rng, seq::SliceRandom};
 use tokio_util::sync::CancellationToken;
 
+use crate::{fenwick::Fenwick, presets::UserPreset};
+
+/// Random number generator of seeded sequences. Unlike `StdRng`, its algorithm is
+/// fixed, so a seed generates the same numbers across versions of `rand`.
+pub type SeededRng = ChaCha8Rng;
+
+/// Creates the generator of the sequences generated from a seed.
+pub fn seeded_rng(seed: u64) -> SeededRng {
+    SeededRng::seed_from_u64(seed)
+}
+
+/// Settings to generate numbers with a target disorder (see [`compute_disorder`]).
 #[derive(PartialEq, Debug, Clone)]
 pub struct DisorderSettings {
+    /// Whether the numbers should match the target disorder.
     pub enabled: bool,
+    /// Whether the numbers are built with exactly the [`Self::target`] disorder, instead of
+    /// being swapped at random until their disorder is within [`Self::range`].
+    pub exact: bool,
+    /// Exact target disorder, from 0 (sorted) to 1 (reverse sorted).
+    pub target: f64,
+    /// Whether the numbers are shuffled before swapping them to match the target disorder.
     pub shuffle: bool,
+    /// Minimum amount of random swaps to perform.
     pub min_swaps: usize,
+    /// Target disorder, from 0 (sorted) to 1 (reverse sorted).
     pub range: RangeInclusive<f64>,
 }
 
@@ -17,6 +45,8 @@ impl Default for DisorderSettings {
     fn default() -> Self {
         Self {
             enabled: false,
+            exact: false,
+            target: 0.5,
             shuffle: true,
             min_swaps: 2000,
             range: 0.2..=0.8,
@@ -24,101 +54,559 @@ impl Default for DisorderSettings {
     }
 }
 
-impl DisorderSettings {
-    pub fn ui(&mut self, ui: &mut Ui, amount: usize) {
-        ui.checkbox(&mut self.enabled, "Generate with target disorder");
-        if self.enabled {
-            ui.checkbox(&mut self.shuffle, "Shuffle before matching disorder");
-            let (mut start, mut end) = (*self.range.start(), *self.range.end());
-            ui.horizontal(|ui| {
-                DragValue::new(&mut self.min_swaps).ui(ui);
-                ui.label("Minimum amount of swaps");
-            });
-            ui.label("Target Disorder:");
-            ui.horizontal(|ui| {
-                DoubleSlider::new(&mut start, &mut end, 0.0..=1.0)
-                    .separation_distance(if amount > 1 { 1. / amount as f64 } else { 1.0 })
-                    .ui(ui);
-                ui.label(format!(
-                    "Min: {:.2}%, Max: {:.2}%",
-                    start * 100.,
-                    end * 100.
-                ));
-            });
-            ui.scope(|ui| {
-                let dark = ui.style().visuals.dark_mode;
-                ui.style_mut().visuals.override_text_color = Some(if dark {
-                    Color32::from_rgb(192, 192, 0)
-                } else {
-                    Color32::from_rgb(140, 140, 0)
-                });
-                ui.label("⚠ Depending on the disorder settings (and bad RNG), numbers may take some time to generate, or could even never generate. If so, press the Kill button and revise your settings.");
-            });
-            self.range = start..=end;
-        }
-    }
-}
-
+/// How numbers to sort are generated.
 #[derive(PartialEq, Clone)]
 pub enum NumberGeneration {
+    /// The given amount of numbers, already sorted.
     Ordered(usize),
+    /// The given amount of numbers, sorted in reverse.
     ReverseOrdered(usize),
+    /// Numbers from 0 to the given amount (excluded), shuffled.
     Random {
+        /// Amount of numbers to generate.
         amount: usize,
+        /// Disorder of the generated numbers.
         disorder: DisorderSettings,
+        /// Seed of the random number generator. A random seed is used if `None`.
+        seed: Option<u64>,
     },
+    /// Distinct random numbers from a range.
     RandomRanged {
+        /// Range the numbers are taken from.
         range: RangeInclusive<i64>,
+        /// Amount of numbers to generate.
         amount: usize,
+        /// Disorder of the generated numbers.
         disorder: DisorderSettings,
+        /// Seed of the random number generator. A random seed is used if `None`.
+        seed: Option<u64>,
     },
+    /// Whitespace-separated numbers.
     Arbitrary(String),
+    /// Numbers read from a text file (see [`parse_numbers_text`]).
+    File(Option<PathBuf>),
+    /// Numbers pasted from the clipboard (see [`parse_numbers_text`]).
+    Clipboard(String),
+    /// One of the [`NUMBER_PRESETS`], by index.
     Preset(usize),
+    /// A preset saved by the user.
+    Saved(UserPreset),
+    /// Numbers following a [`NumberPattern`].
+    Pattern {
+        /// Shape of the numbers.
+        pattern: NumberPattern,
+        /// Amount of numbers to generate.
+        amount: usize,
+        /// Seed of the random number generator. A random seed is used if `None`.
+        seed: Option<u64>,
+    },
+}
+
+/// Parameterized shapes of numbers that are known to trip up sorting algorithms.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum NumberPattern {
+    /// Sorted numbers, with random pairs of numbers swapped.
+    NearlySorted {
+        /// Amount of random swaps.
+        swaps: usize,
+    },
+    /// Shuffled numbers split into sorted runs of the same length.
+    SortedRuns {
+        /// Amount of sorted runs.
+        runs: usize,
+    },
+    /// Ascending ramps, each one going from the lowest to the highest numbers.
+    Sawtooth {
+        /// Amount of ramps.
+        teeth: usize,
+    },
+    /// Numbers going up, then down.
+    OrganPipe,
+    /// Sorted numbers, rotated to the left.
+    Rotated {
+        /// Amount of numbers moved from the top to the bottom.
+        offset: usize,
+    },
+    /// The lower and the upper halves of the sorted numbers, interleaved.
+    InterleavedHalves,
+    /// Sorted numbers, except the last one which belongs anywhere else.
+    LastOutOfPlace,
+    /// Shuffled numbers grouped in clusters of close values, spread over the `int` range.
+    Clustered {
+        /// Amount of clusters.
+        clusters: usize,
+    },
+    /// Shuffled numbers around 0, mixed with numbers as close as possible to `INT_MIN` and `INT_MAX`.
+    Extremes {
+        /// Amount of numbers close to `INT_MIN` or `INT_MAX`.
+        extremes: usize,
+    },
+}
+
+impl NumberPattern {
+    /// Every pattern, with default parameters.
+    pub const ALL: [NumberPattern; 9] = [
+        NumberPattern::NearlySorted { swaps: 5 },
+        NumberPattern::SortedRuns { runs: 4 },
+        NumberPattern::Sawtooth { teeth: 4 },
+        NumberPattern::OrganPipe,
+        NumberPattern::Rotated { offset: 1 },
+        NumberPattern::InterleavedHalves,
+        NumberPattern::LastOutOfPlace,
+        NumberPattern::Clustered { clusters: 4 },
+        NumberPattern::Extremes { extremes: 4 },
+    ];
+
+    /// The name and the value of the parameter of the pattern, if it has one.
+    pub fn parameter_mut(&mut self) -> Option<(&'static str, &mut usize)> {
+        match self {
+            NumberPattern::NearlySorted { swaps } => Some(("Random swaps", swaps)),
+            NumberPattern::SortedRuns { runs } => Some(("Sorted runs", runs)),
+            NumberPattern::Sawtooth { teeth } => Some(("Teeth", teeth)),
+            NumberPattern::Rotated { offset } => Some(("Rotation", offset)),
+            NumberPattern::Clustered { clusters } => Some(("Clusters", clusters)),
+            NumberPattern::Extremes { extremes } => Some(("Extreme numbers", extremes)),
+            NumberPattern::OrganPipe
+            | NumberPattern::InterleavedHalves
+            | NumberPattern::LastOutOfPlace => None,
+        }
+    }
+
+    /// Generates the given amount of distinct numbers following the pattern.
+    pub fn generate(&self, amount: usize, rng: &mut impl Rng) -> Vec<i64> {
+        let n = amount as i64;
+        let mut numbers: Vec<i64> = (0..n).collect();
+        match *self {
+            NumberPattern::NearlySorted { swaps } if amount >= 2 => {
+                for _ in 0..swaps {
+                    let a = rng.random_range(0..amount);
+                    let b = rng.random_range(0..amount);
+                    numbers.swap(a, b);
+                }
+            }
+            NumberPattern::NearlySorted { .. } => {}
+            NumberPattern::SortedRuns { runs } => {
+                numbers.shuffle(rng);
+                let len = amount.div_ceil(runs.clamp(1, amount.max(1))).max(1);
+                for run in numbers.chunks_mut(len) {
+                    run.sort();
+                }
+            }
+            NumberPattern::Sawtooth { teeth } => {
+                let teeth = teeth.clamp(1, amount.max(1)) as i64;
+                numbers = (0..teeth)
+                    .flat_map(|tooth| (tooth..n).step_by(teeth as usize))
+                    .collect();
+            }
+            NumberPattern::OrganPipe => {
+                let odds: Vec<_> = (1..n).step_by(2).collect();
+                numbers = (0..n).step_by(2).chain(odds.into_iter().rev()).collect();
+            }
+            NumberPattern::Rotated { offset } => numbers.rotate_left(offset % amount.max(1)),
+            NumberPattern::InterleavedHalves => {
+                let half = (n + 1) / 2;
+                numbers = (0..n)
+                    .map(|i| if i % 2 == 0 { i / 2 } else { half + i / 2 })
+                    .collect();
+            }
+            NumberPattern::LastOutOfPlace => {
+                if amount >= 2 {
+                    let last = numbers.remove(rng.random_range(0..amount - 1));
+                    numbers.push(last);
+                }
+            }
+            NumberPattern::Clustered { clusters } => {
+                let clusters = clusters.clamp(1, amount.max(1)) as i64;
+                let size = ((n + clusters - 1) / clusters).max(1);
+                let gap = (1 << 32) / clusters;
+                numbers = (0..n)
+                    .map(|i| i32::MIN as i64 + (i / size) * gap + i % size)
+                    .collect();
+                numbers.shuffle(rng);
+            }
+            NumberPattern::Extremes { extremes } => {
+                let extremes = extremes.min(amount) as i64;
+                let small = n - extremes;
+                numbers = (0..small)
+                    .map(|i| i - small / 2)
+                    .chain((0..extremes).map(|i| {
+                        if i % 2 == 0 {
+                            i32::MIN as i64 + i / 2
+                        } else {
+                            i32::MAX as i64 - i / 2
+                        }
+                    }))
+                    .collect();
+                numbers.shuffle(rng);
+            }
+        }
+        numbers
+    }
 }
 
+impl Display for NumberPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            NumberPattern::NearlySorted { .. } => "Nearly Sorted",
+            NumberPattern::SortedRuns { .. } => "Sorted Runs",
+            NumberPattern::Sawtooth { .. } => "Sawtooth",
+            NumberPattern::OrganPipe => "Organ Pipe",
+            NumberPattern::Rotated { .. } => "Rotated",
+            NumberPattern::InterleavedHalves => "Interleaved Halves",
+            NumberPattern::LastOutOfPlace => "Sorted Except Last",
+            NumberPattern::Clustered { .. } => "Clustered Values",
+            NumberPattern::Extremes { .. } => "Mixed with INT_MIN/INT_MAX",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Error returned when numbers could not be generated.
 #[derive(Debug, thiserror::Error)]
 pub enum GenerationError {
+    /// Generation was cancelled with its cancellation token.
     #[error("operation cancelled")]
     Cancelled,
 
-    #[error("failed to parse number: {0}")]
-    Parse(ParseIntError),
+    /// User-provided numbers would be rejected by push_swap.
+    #[error("invalid numbers: {0}")]
+    Invalid(#[from] InputError),
+
+    /// No sequence with exactly the target disorder was drawn, which virtually never happens.
+    #[error("failed to generate numbers with exactly the target disorder, try another seed")]
+    ExactDisorder,
+
+    /// No file to read the numbers from was selected.
+    #[error("no file selected")]
+    NoFile,
+
+    /// The file holding the numbers could not be read.
+    #[error("failed to read numbers from file: {0}")]
+    Read(#[from] io::Error),
 }
 
-pub fn compute_disorder<T>(stack: &[T]) -> f64
+/// Reason a list of numbers would be rejected by push_swap.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum InputError {
+    /// A word is not an optional sign followed by digits.
+    #[error("number {index}: `{word}` is not a number")]
+    NotANumber {
+        /// Position of the word, starting at 1.
+        index: usize,
+        /// The invalid word.
+        word: String,
+    },
+
+    /// A number does not fit in an `int`.
+    #[error("number {index}: {number} is out of the int range")]
+    OutOfRange {
+        /// Position of the number, starting at 1.
+        index: usize,
+        /// The number, as it was written.
+        number: String,
+    },
+
+    /// A number appears more than once.
+    #[error("numbers {first} and {index}: {number} appears twice")]
+    Duplicate {
+        /// Position of the second occurrence, starting at 1.
+        index: usize,
+        /// Position of the first occurrence, starting at 1.
+        first: usize,
+        /// The duplicated number.
+        number: i64,
+    },
+}
+
+/// Checks that numbers fit in an `int` and that none of them is duplicated, like push_swap.
+pub fn check_numbers(numbers: &[i64]) -> Result<(), InputError> {
+    let mut seen = HashMap::new();
+    for (i, &n) in numbers.iter().enumerate() {
+        if i32::try_from(n).is_err() {
+            return Err(InputError::OutOfRange {
+                index: i + 1,
+                number: n.to_string(),
+            });
+        }
+        if let Some(first) = seen.insert(n, i + 1) {
+            return Err(InputError::Duplicate {
+                index: i + 1,
+                first,
+                number: n,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Quoted values of the shell assignments in the text, e.g. `ARG="4 67 3"` or
+/// `ARG = '4 67 3'`, along with the assigned names. Other `=` are skipped.
+fn quoted_assignments(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    text.match_indices('=').filter_map(|(i, _)| {
+        let value = text[i + 1..].trim_start();
+        let quote = value.chars().next().filter(|c| ['"', '\''].contains(c))?;
+        let value = &value[1..];
+        let value = &value[..value.find(quote)?];
+        let name = text[..i].trim_end();
+        let name_start = name
+            .rfind(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .map_or(0, |j| j + 1);
+        Some((&name[name_start..], value))
+    })
+}
+
+/// Parses numbers copied from logs or tester output. Numbers may be separated by
+/// whitespace, newlines or commas. If the text holds a quoted shell assignment,
+/// only the numbers of the first `ARG` assignment are read, or of the first
+/// assignment if none is named `ARG`. The numbers are then checked like
+/// [`parse_numbers`] does.
+pub fn parse_numbers_text(text: &str) -> Result<Vec<i64>, InputError> {
+    let assigned = quoted_assignments(text)
+        .find(|(name, _)| *name == "ARG")
+        .or_else(|| quoted_assignments(text).next())
+        .map(|(_, value)| value);
+    parse_numbers(&assigned.unwrap_or(text).replace(',', " "))
+}
+
+/// Parses whitespace-separated numbers the way push_swap does: each number is
+/// an optional `+` or `-` followed by digits, fits in an `int`, and appears once.
+/// Leading zeros are allowed, so `007` and `7` are duplicates.
+pub fn parse_numbers(s: &str) -> Result<Vec<i64>, InputError> {
+    let numbers = s
+        .split_whitespace()
+        .enumerate()
+        .map(|(i, word)| {
+            let digits = word.strip_prefix(['+', '-']).unwrap_or(word);
+            if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(InputError::NotANumber {
+                    index: i + 1,
+                    word: word.to_string(),
+                });
+            }
+            word.parse::<i32>()
+                .map(i64::from)
+                .map_err(|_| InputError::OutOfRange {
+                    index: i + 1,
+                    number: word.to_string(),
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    check_numbers(&numbers)?;
+    Ok(numbers)
+}
+
+/// Amount of pairs of numbers that are in the wrong order, counted while merge sorting them.
+pub fn count_inversions<T>(stack: &[T]) -> usize
 where
     T: Ord,
 {
-    let mut mistakes = 0;
-    let mut total_pairs = 0;
-    for i in 0..(stack.len() - 1) {
-        for j in (i + 1)..(stack.len() - 1) {
-            total_pairs += 1;
-            if stack[i] > stack[j] {
-                mistakes += 1;
+    fn sort<T: Ord>(numbers: &mut Vec<&T>) -> usize {
+        if numbers.len() < 2 {
+            return 0;
+        }
+        let mut right = numbers.split_off(numbers.len() / 2);
+        let mut left = std::mem::take(numbers);
+        let mut inversions = sort(&mut left) + sort(&mut right);
+        let (mut i, mut j) = (0, 0);
+        while i < left.len() && j < right.len() {
+            if right[j] < left[i] {
+                // Every number left in the left half is greater than this one
+                inversions += left.len() - i;
+                numbers.push(right[j]);
+                j += 1;
+            } else {
+                numbers.push(left[i]);
+                i += 1;
             }
         }
+        numbers.extend_from_slice(&left[i..]);
+        numbers.extend_from_slice(&right[j..]);
+        inversions
+    }
+    sort(&mut stack.iter().collect())
+}
+
+fn pairs(len: usize) -> usize {
+    len * len.saturating_sub(1) / 2
+}
+
+/// Ratio of pairs of numbers that are in the wrong order, from 0 (sorted)
+/// to 1 (reverse sorted).
+pub fn compute_disorder<T>(stack: &[T]) -> f64
+where
+    T: Ord,
+{
+    match pairs(stack.len()) {
+        0 => 0.,
+        pairs => count_inversions(stack) as f64 / pairs as f64,
+    }
+}
+
+/// Swaps two numbers and returns the amount of inversions after the swap, from the amount before it.
+fn swap_inversions(numbers: &mut [i64], a: usize, b: usize, inversions: usize) -> usize {
+    let (i, j) = (a.min(b), a.max(b));
+    if i == j || numbers[i] == numbers[j] {
+        return inversions;
+    }
+    let (low, high) = (numbers[i].min(numbers[j]), numbers[i].max(numbers[j]));
+    // Only the swapped pair and the pairs with a number between them in both position and value change
+    let change: usize = 1 + numbers[i + 1..j]
+        .iter()
+        .map(|n| match (low.cmp(n), n.cmp(&high)) {
+            (Ordering::Less, Ordering::Less) => 2,
+            (Ordering::Equal, _) | (_, Ordering::Equal) => 1,
+            _ => 0,
+        })
+        .sum::<usize>();
+    let sorted_before = numbers[i] < numbers[j];
+    numbers.swap(i, j);
+    if sorted_before {
+        inversions + change
+    } else {
+        inversions - change
+    }
+}
+
+/// Maximum amount of inversion vectors drawn before giving up.
+const MAX_INVERSION_ATTEMPTS: usize = 100_000;
+
+/// Mean of a number from 0 to `max`, drawn with a probability proportional to `exp(theta * number)`.
+fn tilted_mean(max: usize, theta: f64) -> f64 {
+    let m = max as f64;
+    if theta.abs() < 1e-9 {
+        m / 2.
+    } else if theta > 0. {
+        m - tilted_mean(max, -theta)
+    } else {
+        1. / (-theta).exp_m1() - (m + 1.) / (-(m + 1.) * theta).exp_m1()
+    }
+}
+
+/// Draws a number from 0 to `max` with a probability proportional to `exp(theta * number)`.
+fn tilted_sample(max: usize, theta: f64, rng: &mut impl Rng) -> usize {
+    if theta.abs() < 1e-9 {
+        rng.random_range(0..=max)
+    } else if theta > 0. {
+        max - tilted_sample(max, -theta, rng)
+    } else {
+        // Inverse of the cumulative distribution function of a truncated geometric distribution
+        let u: f64 = rng.random();
+        let n = (u * ((max as f64 + 1.) * theta).exp_m1()).ln_1p() / theta;
+        (n.floor() as usize).min(max)
+    }
+}
+
+/// Draws an inversion vector (the `i`-th number being between 0 and `amount - 1 - i`)
+/// uniformly among the ones whose numbers add up to `inversions`.
+///
+/// Every number is drawn independently with a probability proportional to `exp(theta * number)`,
+/// `theta` being chosen so that they add up to `inversions` on average. Every vector with the
+/// right sum is then equally likely, and only the first number needs to be accepted or rejected.
+fn inversion_vector(
+    amount: usize,
+    inversions: usize,
+    rng: &mut impl Rng,
+    token: &CancellationToken,
+) -> Result<Vec<usize>, GenerationError> {
+    if amount < 2 {
+        return Ok(vec![0; amount]);
+    }
+    let mean = |theta| (0..amount).map(|max| tilted_mean(max, theta)).sum::<f64>();
+    let (mut low, mut high) = (-64., 64.);
+    for _ in 0..100 {
+        let middle = (low + high) / 2.;
+        if mean(middle) < inversions as f64 {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    let theta = (low + high) / 2.;
+    let first_max = amount - 1;
+    let most_likely = if theta > 0. { first_max } else { 0 };
+    let mut code = vec![0; amount];
+    for _ in 0..MAX_INVERSION_ATTEMPTS {
+        if token.is_cancelled() {
+            return Err(GenerationError::Cancelled);
+        }
+        let mut sum = 0;
+        for (i, n) in code.iter_mut().enumerate().skip(1) {
+            *n = tilted_sample(amount - 1 - i, theta, rng);
+            sum += *n;
+        }
+        let Some(first) = inversions.checked_sub(sum).filter(|n| *n <= first_max) else {
+            continue;
+        };
+        let acceptance = (theta * (first as f64 - most_likely as f64)).exp();
+        if rng.random::<f64>() < acceptance {
+            code[0] = first;
+            return Ok(code);
+        }
     }
-    mistakes as f64 / total_pairs as f64
+    Err(GenerationError::ExactDisorder)
+}
+
+/// Generates a uniformly random permutation of the numbers from 0 to `amount` (excluded)
+/// with exactly the given amount of inversions (see [`count_inversions`]), capped to the
+/// maximum amount of inversions.
+///
+/// Stops early with [`GenerationError::Cancelled`] once the token is cancelled, and fails
+/// with [`GenerationError::ExactDisorder`] in the virtually impossible case that no
+/// permutation was drawn.
+pub fn permutation_with_inversions(
+    amount: usize,
+    inversions: usize,
+    rng: &mut impl Rng,
+    token: &CancellationToken,
+) -> Result<Vec<usize>, GenerationError> {
+    let code = inversion_vector(amount, inversions.min(pairs(amount)), rng, token)?;
+    // The i-th number is the smallest number left once the next code[i] numbers are skipped
+    let mut left = Fenwick::new(amount);
+    for n in 0..amount {
+        left.add(n as u32, 1);
+    }
+    let permutation = code
+        .into_iter()
+        .map(|n| {
+            let number = left.nth(n);
+            left.add(number, -1);
+            number as usize
+        })
+        .collect();
+    Ok(permutation)
 }
 
 fn generate_with_disorder_cancellable(
     disorder: &DisorderSettings,
     mut numbers: Vec<i64>,
+    rng: &mut impl Rng,
     token: CancellationToken,
 ) -> Result<Vec<i64>, GenerationError> {
+    if disorder.exact {
+        // The numbers are sorted, so their indices can be permuted instead
+        let inversions = (disorder.target * pairs(numbers.len()) as f64).round() as usize;
+        let permutation = permutation_with_inversions(numbers.len(), inversions, rng, &token)?;
+        return Ok(permutation.into_iter().map(|i| numbers[i]).collect());
+    }
     let middle = (disorder.range.start() + disorder.range.end()) / 2.;
     if middle > 0.6666666666666666 {
         numbers.reverse();
     }
     if disorder.shuffle {
-        numbers.shuffle(&mut rand::rng());
+        numbers.shuffle(rng);
+    }
+    let pairs = pairs(numbers.len());
+    if pairs == 0 {
+        return Ok(numbers);
     }
+    let mut inversions = count_inversions(&numbers);
     let mut swaps = 0;
-    while !disorder.range.contains(&compute_disorder(&numbers)) || swaps < disorder.min_swaps {
-        let ra = rand::random_range(0..numbers.len());
-        let rb = rand::random_range(0..numbers.len());
-        numbers.swap(ra, rb);
+    while !disorder.range.contains(&(inversions as f64 / pairs as f64))
+        || swaps < disorder.min_swaps
+    {
+        let ra = rng.random_range(0..numbers.len());
+        let rb = rng.random_range(0..numbers.len());
+        inversions = swap_inversions(&mut numbers, ra, rb, inversions);
         if token.is_cancelled() {
             return Err(GenerationError::Cancelled);
         }
@@ -128,20 +616,41 @@ fn generate_with_disorder_cancellable(
 }
 
 impl NumberGeneration {
+    /// Picks a random seed if the numbers are random and no seed was given, so
+    /// that the same numbers can be generated again. Returns the seed, if any.
+    pub fn resolve_seed(&mut self) -> Option<u64> {
+        match self {
+            NumberGeneration::Random { seed, .. }
+            | NumberGeneration::RandomRanged { seed, .. }
+            | NumberGeneration::Pattern { seed, .. } => {
+                Some(*seed.get_or_insert_with(rand::random))
+            }
+            _ => None,
+        }
+    }
+
+    /// Generates the numbers. Generating with a target disorder can take a while,
+    /// and stops early with [`GenerationError::Cancelled`] once the token is cancelled.
     pub fn get_numbers(&self, token: CancellationToken) -> Result<Vec<i64>, GenerationError> {
         match &self {
             NumberGeneration::Ordered(r) => Ok((0..(*r as i64)).collect()),
             NumberGeneration::ReverseOrdered(r) => Ok((0..(*r as i64)).rev().collect()),
-            NumberGeneration::Random { amount, disorder } => {
+            NumberGeneration::Random {
+                amount,
+                disorder,
+                seed,
+            } => {
+                let mut rng = seeded_rng(seed.unwrap_or_else(rand::random));
                 if disorder.enabled {
                     generate_with_disorder_cancellable(
                         disorder,
                         (0..(*amount as i64)).collect(),
+                        &mut rng,
                         token,
                     )
                 } else {
                     let mut nums: Vec<_> = (0..(*amount as i64)).collect();
-                    nums.shuffle(&mut rand::rng());
+                    nums.shuffle(&mut rng);
                     Ok(nums)
                 }
             }
@@ -149,24 +658,45 @@ impl NumberGeneration {
                 range,
                 amount,
                 disorder,
+                seed,
             } => {
-                let mut map = HashSet::new();
-                while map.len() < *amount {
-                    map.insert(rand::random_range(range.clone()));
+                let mut rng = seeded_rng(seed.unwrap_or_else(rand::random));
+                // The numbers are kept in the order they were picked, since the
+                // iteration order of a set changes between runs
+                let mut picked = HashSet::new();
+                let mut vec = Vec::with_capacity(*amount);
+                while vec.len() < *amount {
+                    let n = rng.random_range(range.clone());
+                    if picked.insert(n) {
+                        vec.push(n);
+                    }
                 }
                 if disorder.enabled {
-                    let mut vec: Vec<_> = map.into_iter().collect();
                     vec.sort();
-                    generate_with_disorder_cancellable(disorder, vec, token)
+                    generate_with_disorder_cancellable(disorder, vec, &mut rng, token)
                 } else {
-                    Ok(map.into_iter().collect())
+                    Ok(vec)
                 }
             }
-            NumberGeneration::Arbitrary(s) => s
-                .split_whitespace()
-                .map(|s| s.parse().map_err(GenerationError::Parse))
-                .collect(),
+            NumberGeneration::Arbitrary(s) => Ok(parse_numbers(s)?),
+            NumberGeneration::File(path) => {
+                let text = fs::read_to_string(path.as_ref().ok_or(GenerationError::NoFile)?)?;
+                Ok(parse_numbers_text(&text)?)
+            }
+            NumberGeneration::Clipboard(text) => Ok(parse_numbers_text(text)?),
             NumberGeneration::Preset(i) => Ok(NUMBER_PRESETS[*i].1.to_vec()),
+            NumberGeneration::Saved(preset) => {
+                check_numbers(&preset.numbers)?;
+                Ok(preset.numbers.clone())
+            }
+            NumberGeneration::Pattern {
+                pattern,
+                amount,
+                seed,
+            } => {
+                let mut rng = seeded_rng(seed.unwrap_or_else(rand::random));
+                Ok(pattern.generate(*amount, &mut rng))
+            }
         }
     }
 }
@@ -175,17 +705,22 @@ impl Display for NumberGeneration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let str = match *self {
             NumberGeneration::Arbitrary(_) => "User Input",
+            NumberGeneration::File(_) => "From File",
+            NumberGeneration::Clipboard(_) => "From Clipboard",
             NumberGeneration::Random { .. } => "Random Normalized",
             NumberGeneration::RandomRanged { .. } => "Random from Custom Range",
             NumberGeneration::Ordered(_) => "Ordered",
             NumberGeneration::ReverseOrdered(_) => "Reverse Ordered",
             NumberGeneration::Preset(_) => "Preset",
+            NumberGeneration::Saved(_) => "Saved Preset",
+            NumberGeneration::Pattern { .. } => "Pattern",
         };
         write!(f, "{}", str)
     }
 }
 
 // NOTE: Presets generated by another 42 student, I do not ake credit for generating these
+/// Named sequences of 100 numbers with interesting shapes.
 #[rustfmt::skip]
 pub const NUMBER_PRESETS: &[(&str, &[i64])] = &[
     ("Pencil", &[2, 4, 6, 8, 10, 12, 14, 16, 18, 20, 22, 24, 26, 28, 30, 32, 34, 36, 38, 40, 42, 44, 46, 48, 50, 52, 54, 56, 58, 60, 62, 64, 66, 68, 70, 72, 74, 76, 78, 80, 82, 84, 86, 88, 90, 92, 94, 96, 98, 100, 99, 97, 95, 93, 91, 89, 87, 85, 83, 81, 79, 77, 75, 73, 71, 69, 67, 65, 63, 61, 59, 57, 55, 53, 51, 49, 47, 45, 43, 41, 39, 37, 35, 33, 31, 29, 27, 25, 23, 21, 19, 17, 15, 13, 11, 9, 7, 5, 3, 1]),
@@ -198,3 +733,250 @@ pub const NUMBER_PRESETS: &[(&str, &[i64])] = &[
     ("2-Way Symetrical Interlocked Ramp", &[100, 1, 98, 3, 96, 5, 94, 7, 92, 9, 90, 11, 88, 13, 86, 15, 84, 17, 82, 19, 80, 21, 78, 23, 76, 25, 74, 27, 72, 29, 70, 31, 68, 33, 66, 35, 64, 37, 62, 39, 60, 41, 58, 43, 56, 45, 54, 47, 52, 49, 50, 51, 48, 53, 46, 55, 44, 57, 42, 59, 40, 61, 38, 63, 36, 65, 34, 67, 32, 69, 30, 71, 28, 73, 26, 75, 24, 77, 22, 79, 20, 81, 18, 83, 16, 85, 14, 87, 12, 89, 10, 91, 8, 93, 6, 95, 4, 97, 2, 99]),
     ("2-Way Decreasing Ramp", &[100, 98, 96, 94, 92, 90, 88, 86, 84, 82, 80, 78, 76, 74, 72, 70, 68, 66, 64, 62, 60, 58, 56, 54, 52, 50, 48, 46, 44, 42, 40, 38, 36, 34, 32, 30, 28, 26, 24, 22, 20, 18, 16, 14, 12, 10, 8, 6, 4, 2, 101, 99, 97, 95, 93, 91, 89, 87, 85, 83, 81, 79, 77, 75, 73, 71, 69, 67, 65, 63, 61, 59, 57, 55, 53, 51, 49, 47, 45, 43, 41, 39, 37, 35, 33, 31, 29, 27, 25, 23, 21, 19, 17, 15, 13, 11, 9, 7, 5, 3, 1]),
 ];
+
+#[cfg(test)]
+mod test {
+    use tokio_util::sync::CancellationToken;
+
+    use rand::RngExt;
+
+    use super::{
+        DisorderSettings, GenerationError, InputError, NumberGeneration, NumberPattern,
+        compute_disorder, count_inversions, pairs, parse_numbers, parse_numbers_text,
+        permutation_with_inversions, seeded_rng, swap_inversions,
+    };
+
+    fn brute_force_inversions(numbers: &[i64]) -> usize {
+        let mut inversions = 0;
+        for i in 0..numbers.len() {
+            for j in (i + 1)..numbers.len() {
+                if numbers[i] > numbers[j] {
+                    inversions += 1;
+                }
+            }
+        }
+        inversions
+    }
+
+    #[test]
+    fn test_inversions() {
+        let mut rng = seeded_rng(19);
+        for len in [0, 1, 2, 3, 10, 100] {
+            let mut numbers: Vec<i64> = (0..len).map(|_| rng.random_range(-20..=20)).collect();
+            let mut inversions = count_inversions(&numbers);
+            assert_eq!(inversions, brute_force_inversions(&numbers));
+            for _ in 0..200.min(len * len) {
+                let a = rng.random_range(0..numbers.len());
+                let b = rng.random_range(0..numbers.len());
+                inversions = swap_inversions(&mut numbers, a, b, inversions);
+                assert_eq!(inversions, brute_force_inversions(&numbers));
+            }
+        }
+        assert_eq!(compute_disorder(&[3, 2, 1]), 1.);
+        assert_eq!(compute_disorder(&[1, 3, 2]), 1. / 3.);
+        assert_eq!(compute_disorder::<i64>(&[]), 0.);
+    }
+
+    #[test]
+    fn test_seeded_numbers() {
+        // A seed must generate the same numbers in every version of psv
+        let random = NumberGeneration::Random {
+            amount: 10,
+            disorder: DisorderSettings::default(),
+            seed: Some(42),
+        };
+        let numbers = random.get_numbers(CancellationToken::new()).unwrap();
+        assert_eq!(numbers, [7, 1, 5, 4, 6, 9, 3, 8, 2, 0]);
+        let ranged = NumberGeneration::RandomRanged {
+            range: -1000..=1000,
+            amount: 5,
+            disorder: DisorderSettings::default(),
+            seed: Some(42),
+        };
+        let numbers = ranged.get_numbers(CancellationToken::new()).unwrap();
+        assert_eq!(numbers, [364, 901, -145, 255, -423]);
+    }
+
+    #[test]
+    fn test_exact_inversions() {
+        let mut rng = seeded_rng(20);
+        let token = CancellationToken::new();
+        for amount in [0, 1, 2, 5, 50, 500] {
+            let max = pairs(amount);
+            for inversions in [0, 1, max / 3, max / 2, max.saturating_sub(1), max] {
+                let permutation =
+                    permutation_with_inversions(amount, inversions, &mut rng, &token).unwrap();
+                let mut sorted = permutation.clone();
+                sorted.sort();
+                assert_eq!(sorted, (0..amount).collect::<Vec<_>>());
+                assert_eq!(count_inversions(&permutation), inversions.min(max));
+            }
+        }
+        // Every permutation of 4 numbers with 3 inversions should be as likely
+        let mut counts = std::collections::HashMap::new();
+        for _ in 0..6000 {
+            *counts
+                .entry(permutation_with_inversions(4, 3, &mut rng, &token).unwrap())
+                .or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 6);
+        assert!(counts.values().all(|count| (800..1200).contains(count)));
+
+        token.cancel();
+        assert!(matches!(
+            permutation_with_inversions(50, 600, &mut rng, &token),
+            Err(GenerationError::Cancelled)
+        ));
+    }
+
+    #[test]
+    fn test_seeded_generation() {
+        let random = NumberGeneration::Random {
+            amount: 50,
+            disorder: DisorderSettings::default(),
+            seed: Some(42),
+        };
+        let ranged = NumberGeneration::RandomRanged {
+            range: -1000..=1000,
+            amount: 50,
+            disorder: DisorderSettings {
+                enabled: true,
+                ..Default::default()
+            },
+            seed: Some(42),
+        };
+        for generation in [random, ranged] {
+            let numbers = generation.get_numbers(CancellationToken::new()).unwrap();
+            assert_eq!(numbers.len(), 50);
+            assert_eq!(
+                generation.get_numbers(CancellationToken::new()).unwrap(),
+                numbers
+            );
+            let mut resolved = generation.clone();
+            assert_eq!(resolved.resolve_seed(), Some(42));
+        }
+        let mut unseeded = NumberGeneration::Random {
+            amount: 50,
+            disorder: DisorderSettings::default(),
+            seed: None,
+        };
+        let seed = unseeded.resolve_seed();
+        assert!(seed.is_some());
+        assert_eq!(unseeded.resolve_seed(), seed);
+    }
+
+    #[test]
+    fn test_patterns() {
+        let mut rng = seeded_rng(21);
+        for pattern in NumberPattern::ALL {
+            for amount in [0, 1, 2, 5, 100] {
+                let mut numbers = pattern.generate(amount, &mut rng);
+                assert_eq!(numbers.len(), amount, "{}", pattern);
+                assert!(
+                    numbers
+                        .iter()
+                        .all(|n| (i32::MIN as i64..=i32::MAX as i64).contains(n))
+                );
+                numbers.sort();
+                numbers.dedup();
+                assert_eq!(numbers.len(), amount, "{} has duplicates", pattern);
+            }
+        }
+        assert_eq!(
+            NumberPattern::OrganPipe.generate(5, &mut rng),
+            [0, 2, 4, 3, 1]
+        );
+        assert_eq!(
+            NumberPattern::Rotated { offset: 2 }.generate(5, &mut rng),
+            [2, 3, 4, 0, 1]
+        );
+        assert_eq!(
+            NumberPattern::Sawtooth { teeth: 3 }.generate(9, &mut rng),
+            [0, 3, 6, 1, 4, 7, 2, 5, 8]
+        );
+        assert_eq!(
+            NumberPattern::InterleavedHalves.generate(5, &mut rng),
+            [0, 3, 1, 4, 2]
+        );
+        let extremes = NumberPattern::Extremes { extremes: 2 }.generate(10, &mut rng);
+        assert!(extremes.contains(&(i32::MIN as i64)));
+        assert!(extremes.contains(&(i32::MAX as i64)));
+    }
+
+    #[test]
+    fn test_parse_numbers() {
+        assert_eq!(
+            parse_numbers(" 3 -2\t+7 0010 -2147483648 2147483647\n").unwrap(),
+            [3, -2, 7, 10, -2147483648, 2147483647]
+        );
+        assert!(parse_numbers("").unwrap().is_empty());
+        for word in ["+", "-", "--", "+-1", "1a", "1.5", "0x10"] {
+            assert_eq!(
+                parse_numbers(&format!("1 {}", word)),
+                Err(InputError::NotANumber {
+                    index: 2,
+                    word: word.to_string()
+                })
+            );
+        }
+        for number in ["2147483648", "-2147483649", "99999999999999999999"] {
+            assert!(matches!(
+                parse_numbers(number),
+                Err(InputError::OutOfRange { index: 1, .. })
+            ));
+        }
+        assert_eq!(
+            parse_numbers("5 1 2 001"),
+            Err(InputError::Duplicate {
+                index: 4,
+                first: 2,
+                number: 1
+            })
+        );
+        assert!(parse_numbers("0 -0").is_err());
+        assert!(
+            NumberGeneration::Arbitrary("1 1".into())
+                .get_numbers(CancellationToken::new())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_numbers_text() {
+        let expected = [4, 67, -3, 87, 23];
+        for text in [
+            "4 67 -3 87 23",
+            "4\n67\n-3\n87\n23\n",
+            "4, 67,-3 ,87,\n23",
+            "ARG=\"4 67 -3 87 23\"; ./push_swap $ARG | ./checker_linux $ARG",
+            "export ARG='4 67 -3 87 23'",
+        ] {
+            assert_eq!(parse_numbers_text(text).unwrap(), expected, "{:?}", text);
+        }
+        for text in [
+            "==== ARG=\"4 67 -3 87 23\" ====",
+            "FOO=bar ARG=\"4 67 -3 87 23\"",
+            "make test CFLAGS=-O2 ARG=\"4 67 -3 87 23\"",
+            "NAME=\"push_swap\" ARG='4 67 -3 87 23'",
+            "ARG = \"4 67 -3 87 23\"",
+            "x == y; ARG=\"4 67 -3 87 23\"",
+        ] {
+            assert_eq!(parse_numbers_text(text).unwrap(), expected, "{:?}", text);
+        }
+        assert!(parse_numbers_text("4,,67").is_ok());
+        assert!(parse_numbers_text("ARG=\"1 2 1\"").is_err());
+        assert!(parse_numbers_text("Numbers: 1 2").is_err());
+
+        let path = std::env::temp_dir().join(format!("psv-numbers-{}.txt", std::process::id()));
+        std::fs::write(&path, "3,\n1,\n2\n").unwrap();
+        let numbers =
+            NumberGeneration::File(Some(path.clone())).get_numbers(CancellationToken::new());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(numbers.unwrap(), [3, 1, 2]);
+        assert!(
+            NumberGeneration::File(None)
+                .get_numbers(CancellationToken::new())
+                .is_err()
+        );
+    }
+}
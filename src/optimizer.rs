@@ -0,0 +1,171 @@
+//! Peephole optimization of push_swap instructions.
+
+use crate::sim::{PushSwapInstruction, PushSwapSim, Stack};
+
+enum Rewrite {
+    Merge(PushSwapInstruction),
+    Cancel,
+}
+
+struct Entry {
+    instruction: PushSwapInstruction,
+    effective: bool,
+    origins: Vec<usize>,
+}
+
+/// Result of a peephole optimization pass over a list of instructions.
+#[derive(Debug, Clone, Default)]
+pub struct Optimization {
+    /// The optimized instructions.
+    pub instructions: Vec<PushSwapInstruction>,
+    /// For every original instruction, whether it was merged or cancelled out.
+    pub rewritten: Vec<bool>,
+}
+
+impl Optimization {
+    /// Amount of instructions that were saved by the optimization.
+    pub fn saved(&self) -> usize {
+        self.rewritten.len() - self.instructions.len()
+    }
+}
+
+fn rewrite(prev: &Entry, next: PushSwapInstruction) -> Option<Rewrite> {
+    use PushSwapInstruction::*;
+    match (prev.instruction, next) {
+        (RotateA, RotateB) | (RotateB, RotateA) => Some(Rewrite::Merge(RotateBoth)),
+        (ReverseRotateA, ReverseRotateB) | (ReverseRotateB, ReverseRotateA) => {
+            Some(Rewrite::Merge(ReverseRotateBoth))
+        }
+        (SwapA, SwapB) | (SwapB, SwapA) => Some(Rewrite::Merge(SwapBoth)),
+        // A push only cancels out if it actually moved a number
+        (PushA, PushB) | (PushB, PushA) if prev.effective => Some(Rewrite::Cancel),
+        (RotateA, ReverseRotateA)
+        | (ReverseRotateA, RotateA)
+        | (RotateB, ReverseRotateB)
+        | (ReverseRotateB, RotateB)
+        | (RotateBoth, ReverseRotateBoth)
+        | (ReverseRotateBoth, RotateBoth)
+        | (SwapA, SwapA)
+        | (SwapB, SwapB)
+        | (SwapBoth, SwapBoth) => Some(Rewrite::Cancel),
+        _ => None,
+    }
+}
+
+fn final_stacks(numbers: &[u32], instructions: &[PushSwapInstruction]) -> (Vec<u32>, Vec<u32>) {
+    let mut sim = PushSwapSim::default();
+    sim.load_instructions(numbers.to_vec(), instructions.to_vec());
+    while sim.step() {}
+    sim.make_contiguous();
+    (sim.stack_a().to_vec(), sim.stack_b().to_vec())
+}
+
+/// Merges and cancels out adjacent instructions when doing so cannot change
+/// the final state of the stacks.
+///
+/// The result is verified by simulating both instruction lists: if the final
+/// stacks differ, the original instructions are returned untouched.
+pub fn optimize(numbers: &[u32], instructions: &[PushSwapInstruction]) -> Optimization {
+    let mut stack_a: Stack = numbers.iter().copied().collect();
+    let mut stack_b = Stack::new();
+    let mut output: Vec<Entry> = vec![];
+    let mut rewritten = vec![false; instructions.len()];
+    for (i, &ins) in instructions.iter().enumerate() {
+        let effective = ins.execute(&mut stack_a, &mut stack_b);
+        match output
+            .last_mut()
+            .and_then(|prev| rewrite(prev, ins).map(|r| (prev, r)))
+        {
+            Some((prev, Rewrite::Merge(merged))) => {
+                prev.instruction = merged;
+                prev.origins.push(i);
+                for &origin in &prev.origins {
+                    rewritten[origin] = true;
+                }
+            }
+            Some((_, Rewrite::Cancel)) => {
+                let prev = output.pop().expect("rewrite without previous instruction");
+                for origin in prev.origins {
+                    rewritten[origin] = true;
+                }
+                rewritten[i] = true;
+            }
+            None => output.push(Entry {
+                instruction: ins,
+                effective,
+                origins: vec![i],
+            }),
+        }
+    }
+    let optimized: Vec<_> = output.into_iter().map(|e| e.instruction).collect();
+    if final_stacks(numbers, &optimized) != final_stacks(numbers, instructions) {
+        return Optimization {
+            instructions: instructions.to_vec(),
+            rewritten: vec![false; instructions.len()],
+        };
+    }
+    Optimization {
+        instructions: optimized,
+        rewritten,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{final_stacks, optimize};
+    use crate::{
+        parser::parse_push_swap,
+        sim::{PushSwapInstruction, PushSwapInstruction::*},
+    };
+
+    #[test]
+    fn merges() {
+        let ins = parse_push_swap("ra rb rrb rra sb sa pb").unwrap();
+        let res = optimize(&[3, 1, 2, 0], &ins);
+        assert_eq!(
+            res.instructions,
+            &[RotateBoth, ReverseRotateBoth, SwapBoth, PushB]
+        );
+        assert_eq!(res.saved(), 3);
+        assert_eq!(res.rewritten, &[true, true, true, true, true, true, false]);
+    }
+
+    #[test]
+    fn cascading_cancels() {
+        let ins = parse_push_swap("pb ra sa sa rra pa sa").unwrap();
+        let res = optimize(&[1, 0, 2], &ins);
+        assert_eq!(res.instructions, &[SwapA]);
+        assert_eq!(res.saved(), 6);
+    }
+
+    #[test]
+    fn noop_push_is_kept() {
+        // pa does nothing on an empty stack B, so pb still has to run
+        let ins = parse_push_swap("pa pb").unwrap();
+        let res = optimize(&[0, 1], &ins);
+        assert_eq!(res.instructions, ins);
+        assert_eq!(res.saved(), 0);
+    }
+
+    #[test]
+    fn random_streams_keep_final_stacks() {
+        use rand::{
+            SeedableRng,
+            rngs::StdRng,
+            seq::{IndexedRandom, SliceRandom},
+        };
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..200 {
+            let mut numbers: Vec<u32> = (0..6).collect();
+            numbers.shuffle(&mut rng);
+            let ins: Vec<_> = (0..40)
+                .map(|_| *PushSwapInstruction::ALL.choose(&mut rng).unwrap())
+                .collect();
+            let res = optimize(&numbers, &ins);
+            assert_eq!(
+                final_stacks(&numbers, &res.instructions),
+                final_stacks(&numbers, &ins)
+            );
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Optimal solutions for small amounts of numbers.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::sim::{PushSwapInstruction, Stack};
+
+/// Maximum amount of numbers the oracle can find optimal solutions for.
+///
+/// The amount of states to explore grows with `n! * (n + 1)`.
+pub const MAX_ORACLE_NUMBERS: usize = 7;
+
+const BITS_PER_NUMBER: usize = 4;
+
+fn encode(stack_a: &Stack, stack_b: &Stack) -> u64 {
+    let packed = stack_a
+        .iter()
+        .chain(stack_b)
+        .fold(0, |acc, n| (acc << BITS_PER_NUMBER) | *n as u64);
+    ((stack_a.len() as u64) << 60) | packed
+}
+
+fn decode(state: u64, amount: usize) -> (Stack, Stack) {
+    let len_a = (state >> 60) as usize;
+    let mask = (1 << BITS_PER_NUMBER) - 1;
+    let numbers: Vec<_> = (0..amount)
+        .rev()
+        .map(|i| ((state >> (i * BITS_PER_NUMBER)) & mask) as u32)
+        .collect();
+    (
+        numbers[..len_a].iter().copied().collect(),
+        numbers[len_a..].iter().copied().collect(),
+    )
+}
+
+fn neighbours(state: u64, amount: usize) -> impl Iterator<Item = (PushSwapInstruction, u64)> {
+    let (stack_a, stack_b) = decode(state, amount);
+    PushSwapInstruction::ALL.into_iter().filter_map(move |ins| {
+        let (mut a, mut b) = (stack_a.clone(), stack_b.clone());
+        ins.execute(&mut a, &mut b).then(|| (ins, encode(&a, &b)))
+    })
+}
+
+fn sorted_state(amount: usize) -> u64 {
+    encode(&(0..amount as u32).collect(), &Stack::new())
+}
+
+/// Checks that the numbers are a permutation of the numbers from `0` to `len - 1`.
+fn valid_input(numbers: &[u32]) -> bool {
+    if numbers.len() > MAX_ORACLE_NUMBERS {
+        return false;
+    }
+    let mut seen = 0u32;
+    for &n in numbers {
+        if n as usize >= numbers.len() || seen & (1 << n) != 0 {
+            return false;
+        }
+        seen |= 1 << n;
+    }
+    true
+}
+
+/// Finds one of the shortest lists of instructions sorting the given normalized numbers.
+///
+/// Returns `None` if there are more than [`MAX_ORACLE_NUMBERS`] numbers, or if they
+/// are not a permutation of the numbers from `0` to `len - 1`.
+pub fn optimal_solution(numbers: &[u32]) -> Option<Vec<PushSwapInstruction>> {
+    if !valid_input(numbers) {
+        return None;
+    }
+    let amount = numbers.len();
+    let start = encode(&numbers.iter().copied().collect(), &Stack::new());
+    let goal = sorted_state(amount);
+    let mut parents: HashMap<u64, (u64, PushSwapInstruction)> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(state) = queue.pop_front() {
+        if state == goal {
+            break;
+        }
+        for (ins, next) in neighbours(state, amount) {
+            if next != start && !parents.contains_key(&next) {
+                parents.insert(next, (state, ins));
+                queue.push_back(next);
+            }
+        }
+    }
+    let mut solution = vec![];
+    let mut state = goal;
+    while state != start {
+        let (parent, ins) = *parents.get(&state)?;
+        solution.push(ins);
+        state = parent;
+    }
+    solution.reverse();
+    Some(solution)
+}
+
+/// Optimal amount of instructions for every permutation of `n` numbers.
+pub struct OptimalTable {
+    amount: usize,
+    distances: HashMap<u64, usize>,
+}
+
+impl OptimalTable {
+    /// Explores every state reachable from the sorted stack. Since every instruction
+    /// that changes the stacks can be reverted by another one, the distance from the
+    /// sorted state is also the distance to it.
+    ///
+    /// Returns `None` if `amount` is greater than [`MAX_ORACLE_NUMBERS`].
+    pub fn new(amount: usize) -> Option<Self> {
+        if amount > MAX_ORACLE_NUMBERS {
+            return None;
+        }
+        let goal = sorted_state(amount);
+        let mut distances = HashMap::from([(goal, 0)]);
+        let mut queue = VecDeque::from([goal]);
+        while let Some(state) = queue.pop_front() {
+            let distance = distances[&state];
+            for (_, next) in neighbours(state, amount) {
+                distances.entry(next).or_insert_with(|| {
+                    queue.push_back(next);
+                    distance + 1
+                });
+            }
+        }
+        Some(Self { amount, distances })
+    }
+
+    /// Optimal amount of instructions to sort the given normalized numbers.
+    pub fn distance(&self, numbers: &[u32]) -> Option<usize> {
+        if numbers.len() != self.amount || !valid_input(numbers) {
+            return None;
+        }
+        let state = encode(&numbers.iter().copied().collect(), &Stack::new());
+        self.distances.get(&state).copied()
+    }
+}
+
+/// Every permutation of the numbers from `0` to `amount - 1`, in lexicographic order.
+pub fn permutations(amount: usize) -> Vec<Vec<u32>> {
+    let mut current: Vec<u32> = (0..amount as u32).collect();
+    let mut all = vec![current.clone()];
+    loop {
+        let Some(i) = current.windows(2).rposition(|w| w[0] < w[1]) else {
+            return all;
+        };
+        let j = current
+            .iter()
+            .rposition(|n| *n > current[i])
+            .expect("no successor");
+        current.swap(i, j);
+        current[i + 1..].reverse();
+        all.push(current.clone());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{OptimalTable, optimal_solution, permutations};
+    use crate::sim::PushSwapSim;
+
+    #[test]
+    fn three_numbers() {
+        let table = OptimalTable::new(3).unwrap();
+        let expected = [0, 2, 1, 1, 1, 2];
+        for (numbers, expected) in permutations(3).iter().zip(expected) {
+            assert_eq!(table.distance(numbers), Some(expected), "{:?}", numbers);
+        }
+    }
+
+    #[test]
+    fn solutions_sort_and_match_table() {
+        let table = OptimalTable::new(5).unwrap();
+        assert_eq!(permutations(5).len(), 120);
+        for numbers in permutations(5).iter().step_by(7) {
+            let solution = optimal_solution(numbers).unwrap();
+            assert_eq!(Some(solution.len()), table.distance(numbers));
+            let mut sim = PushSwapSim::default();
+            sim.load_instructions(numbers.clone(), solution);
+            while sim.step() {}
+            sim.make_contiguous();
+            assert!(sim.stack_a().is_sorted());
+            assert_eq!(sim.stack_a().len(), 5);
+        }
+    }
+
+    #[test]
+    fn too_many_numbers() {
+        assert!(optimal_solution(&[7, 6, 5, 4, 3, 2, 1, 0]).is_none());
+        assert!(OptimalTable::new(8).is_none());
+    }
+
+    #[test]
+    fn not_a_permutation() {
+        assert!(optimal_solution(&[0, 0]).is_none());
+        assert!(optimal_solution(&[0, 2]).is_none());
+        let table = OptimalTable::new(3).unwrap();
+        assert_eq!(table.distance(&[1, 1, 0]), None);
+    }
+}
@@ -0,0 +1,251 @@
+//! Parsing and formatting of push_swap output.
+
+use crate::sim::PushSwapInstruction;
+
+/// Formats instructions the way a push_swap program would print them, one per line.
+pub fn format_instructions(instructions: &[PushSwapInstruction]) -> String {
+    instructions
+        .iter()
+        .map(|ins| format!("{}\n", ins))
+        .collect()
+}
+
+/// Error returned when push_swap output contains something that is not an instruction.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("line {line}, column {column}: `{line_text}` is not a valid push_swap instruction")]
+pub struct ParseError {
+    /// The invalid whitespace-separated token.
+    pub token: String,
+    /// Index of the token among all tokens, starting at 1.
+    pub index: usize,
+    /// Line of the token in the raw output, starting at 1.
+    pub line: usize,
+    /// Column of the token in its line, in characters, starting at 1.
+    pub column: usize,
+    /// Full content of the line containing the token.
+    pub line_text: String,
+    /// The lines surrounding the invalid token, with their line numbers.
+    pub context: Vec<(usize, String)>,
+}
+
+impl ParseError {
+    const CONTEXT_LINES: usize = 2;
+
+    fn new(text: &str, token: &str, index: usize, line: usize, column: usize) -> Self {
+        let first = line.saturating_sub(Self::CONTEXT_LINES).max(1);
+        let context: Vec<_> = text
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l.to_string()))
+            .skip(first - 1)
+            .take(line - first + Self::CONTEXT_LINES + 1)
+            .collect();
+        let line_text = context
+            .iter()
+            .find(|(i, _)| *i == line)
+            .map(|(_, l)| l.clone())
+            .unwrap_or_default();
+        Self {
+            token: token.to_string(),
+            index,
+            line,
+            column,
+            line_text,
+            context,
+        }
+    }
+
+    /// Formats the surrounding lines, pointing at the invalid token.
+    pub fn snippet(&self) -> String {
+        let width = self
+            .context
+            .last()
+            .map(|(i, _)| i.to_string().len())
+            .unwrap_or(1);
+        let mut snippet = String::new();
+        for (i, line) in &self.context {
+            snippet += &format!("{:>width$} | {}\n", i, line, width = width);
+            if *i == self.line {
+                snippet += &format!(
+                    "{:>width$} | {}{}\n",
+                    "",
+                    " ".repeat(self.column - 1),
+                    "^".repeat(self.token.chars().count()),
+                    width = width
+                );
+            }
+        }
+        snippet
+    }
+}
+
+/// Parses a single instruction, as printed by a push_swap program.
+pub fn parse_instruction(ins: &str) -> Option<PushSwapInstruction> {
+    use PushSwapInstruction::*;
+    let ins = match ins {
+        "sa" => SwapA,
+        "sb" => SwapB,
+        "ss" => SwapBoth,
+        "pa" => PushA,
+        "pb" => PushB,
+        "ra" => RotateA,
+        "rb" => RotateB,
+        "rr" => RotateBoth,
+        "rra" => ReverseRotateA,
+        "rrb" => ReverseRotateB,
+        "rrr" => ReverseRotateBoth,
+        _ => return None,
+    };
+    Some(ins)
+}
+
+/// Splits a line into whitespace-separated tokens, along with their column.
+fn tokens(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut column = 0;
+    line.split(char::is_whitespace).filter_map(move |token| {
+        let token_column = column + 1;
+        column += token.chars().count() + 1;
+        (!token.is_empty()).then_some((token_column, token))
+    })
+}
+
+/// Parses whitespace-separated instructions, as printed by a push_swap program.
+///
+/// This is lenient: instructions may be separated by any whitespace. See
+/// [`check_strict_format`] to check the output format itself.
+pub fn parse_push_swap(text: &str) -> Result<Vec<PushSwapInstruction>, ParseError> {
+    let mut instructions = vec![];
+    for (line, raw) in text.lines().enumerate() {
+        for (column, token) in tokens(raw) {
+            match parse_instruction(token) {
+                Some(ins) => instructions.push(ins),
+                None => {
+                    let index = instructions.len() + 1;
+                    return Err(ParseError::new(text, token, index, line + 1, column));
+                }
+            }
+        }
+    }
+    Ok(instructions)
+}
+
+/// A deviation from the strict push_swap output format.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FormatIssueKind {
+    /// A line contains no instruction.
+    #[error("empty line")]
+    EmptyLine,
+
+    /// A line ends with `\r\n`.
+    #[error("line ends with \\r\\n instead of \\n")]
+    CarriageReturn,
+
+    /// An instruction is surrounded by whitespace.
+    #[error("leading or trailing whitespace")]
+    ExtraWhitespace,
+
+    /// A line contains several instructions.
+    #[error("{0} instructions on the same line")]
+    SeveralInstructions(usize),
+
+    /// A line contains something that is not an instruction.
+    #[error("`{0}` is not a valid push_swap instruction")]
+    InvalidInstruction(String),
+
+    /// The last instruction is not followed by a newline.
+    #[error("missing newline at the end of the output")]
+    MissingNewline,
+}
+
+/// A deviation from the strict push_swap output format, along with its line.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("line {line}: {kind}")]
+pub struct FormatIssue {
+    /// Line of the issue, starting at 1.
+    pub line: usize,
+    /// The kind of issue.
+    pub kind: FormatIssueKind,
+}
+
+/// Checks that the text contains exactly one instruction per line, each
+/// terminated by a `\n`, like the 42 checker expects. Returns every deviation.
+pub fn check_strict_format(text: &str) -> Vec<FormatIssue> {
+    let mut issues = vec![];
+    let mut lines: Vec<_> = text.split('\n').collect();
+    let missing_newline = lines.pop().is_some_and(|last| !last.is_empty());
+    if missing_newline {
+        lines.push(&text[text.rfind('\n').map(|i| i + 1).unwrap_or(0)..]);
+    }
+    for (i, raw) in lines.iter().enumerate() {
+        let line = i + 1;
+        let mut issue = |kind| issues.push(FormatIssue { line, kind });
+        let content = match raw.strip_suffix('\r') {
+            Some(content) => {
+                issue(FormatIssueKind::CarriageReturn);
+                content
+            }
+            None => raw,
+        };
+        let tokens: Vec<_> = content.split_whitespace().collect();
+        match tokens.as_slice() {
+            [] => issue(FormatIssueKind::EmptyLine),
+            [token] if *token != content => issue(FormatIssueKind::ExtraWhitespace),
+            [_] => {}
+            _ => issue(FormatIssueKind::SeveralInstructions(tokens.len())),
+        }
+        for token in tokens {
+            if parse_instruction(token).is_none() {
+                issue(FormatIssueKind::InvalidInstruction(token.to_string()));
+            }
+        }
+    }
+    if missing_newline {
+        issues.push(FormatIssue {
+            line: lines.len(),
+            kind: FormatIssueKind::MissingNewline,
+        });
+    }
+    issues
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FormatIssueKind::*, check_strict_format, parse_push_swap};
+
+    #[test]
+    fn test_parse_error() {
+        let text = "pb\nra\r\n\nrra  rb\ndebug: 3\npa\n";
+        let err = parse_push_swap(text).unwrap_err();
+        assert_eq!(err.token, "debug:");
+        assert_eq!(err.index, 5);
+        assert_eq!(err.line, 5);
+        assert_eq!(err.column, 1);
+        assert_eq!(err.line_text, "debug: 3");
+        assert_eq!(err.context.len(), 4);
+        assert_eq!(err.context[0], (3, String::new()));
+
+        let err = parse_push_swap("sa\n  sb  xx").unwrap_err();
+        assert_eq!((err.line, err.column, err.index), (2, 7, 3));
+        assert_eq!(err.snippet(), "1 | sa\n2 |   sb  xx\n  |       ^^\n");
+    }
+    #[test]
+    fn test_strict_format() {
+        assert!(check_strict_format("").is_empty());
+        assert!(check_strict_format("pb\nra\npa\n").is_empty());
+        let issues: Vec<_> = check_strict_format("pb ra\n\nsa\r\n rb\nxx\npa")
+            .into_iter()
+            .map(|issue| (issue.line, issue.kind))
+            .collect();
+        assert_eq!(
+            issues,
+            &[
+                (1, SeveralInstructions(2)),
+                (2, EmptyLine),
+                (3, CarriageReturn),
+                (4, ExtraWhitespace),
+                (5, InvalidInstruction("xx".into())),
+                (6, MissingNewline),
+            ]
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Named sequences of numbers saved by the user, and the files they are shared with.
+//!
+//! In plain text, every non-empty line holds a preset as `name: numbers`, the numbers being
+//! separated by spaces. Lines starting with `#` are comments.
+
+use std::{
+    fmt::Display,
+    io::{self, Write},
+    num::ParseIntError,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// A named sequence of numbers.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserPreset {
+    /// Name shown in the list of presets.
+    pub name: String,
+    /// Numbers of the preset, top of stack A first.
+    pub numbers: Vec<i64>,
+}
+
+/// File format of a collection of presets.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PresetFormat {
+    /// A JSON array of presets.
+    #[default]
+    Json,
+    /// One preset per line.
+    Text,
+}
+
+impl PresetFormat {
+    /// Every preset format.
+    pub const ALL: [PresetFormat; 2] = [PresetFormat::Json, PresetFormat::Text];
+
+    /// File extension of the format, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            PresetFormat::Json => "json",
+            PresetFormat::Text => "txt",
+        }
+    }
+
+    /// Guesses the format from the extension of a path, defaulting to plain text.
+    pub fn from_path(path: &Path) -> Self {
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        if is_json {
+            PresetFormat::Json
+        } else {
+            PresetFormat::Text
+        }
+    }
+}
+
+impl Display for PresetFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            PresetFormat::Json => "JSON",
+            PresetFormat::Text => "Plain Text",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Error returned when a collection of presets could not be read.
+#[derive(Debug, thiserror::Error)]
+pub enum PresetError {
+    /// The JSON file is malformed.
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// A line of a plain text file has no name.
+    #[error("line {0}: expected `name: numbers`")]
+    MissingName(usize),
+
+    /// A number of a plain text file is not valid.
+    #[error("line {line}: failed to parse number: {source}")]
+    Parse {
+        /// Line of the number, starting at 1.
+        line: usize,
+        /// Reason the number is not valid.
+        source: ParseIntError,
+    },
+}
+
+/// Reads a collection of presets.
+pub fn read_presets(text: &str, format: PresetFormat) -> Result<Vec<UserPreset>, PresetError> {
+    match format {
+        PresetFormat::Json => Ok(serde_json::from_str(text)?),
+        PresetFormat::Text => text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(i, line)| {
+                // Numbers never contain a colon, unlike names
+                let (name, numbers) = line
+                    .rsplit_once(':')
+                    .ok_or(PresetError::MissingName(i + 1))?;
+                let numbers = numbers
+                    .split_whitespace()
+                    .map(|n| {
+                        n.parse().map_err(|source| PresetError::Parse {
+                            line: i + 1,
+                            source,
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(UserPreset {
+                    name: name.trim().to_string(),
+                    numbers,
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Writes a collection of presets.
+pub fn write_presets(
+    presets: &[UserPreset],
+    format: PresetFormat,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    match format {
+        PresetFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, presets)?;
+            writeln!(writer)?;
+        }
+        PresetFormat::Text => {
+            for preset in presets {
+                let numbers: Vec<_> = preset.numbers.iter().map(i64::to_string).collect();
+                writeln!(writer, "{}: {}", preset.name, numbers.join(" "))?;
+            }
+        }
+    }
+    writer.flush()
+}
+
+/// Adds imported presets to a collection, replacing the presets with the same name.
+pub fn merge_presets(presets: &mut Vec<UserPreset>, imported: Vec<UserPreset>) {
+    for preset in imported {
+        match presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => presets.push(preset),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        PresetError, PresetFormat, UserPreset, merge_presets, read_presets, write_presets,
+    };
+
+    fn preset(name: &str, numbers: &[i64]) -> UserPreset {
+        UserPreset {
+            name: name.to_string(),
+            numbers: numbers.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_presets() {
+        let presets = vec![
+            preset("Broke rotations", &[3, -1, 2147483647]),
+            preset("Ratio 1:2", &[0, 1]),
+            preset("Empty", &[]),
+        ];
+        for format in PresetFormat::ALL {
+            let mut file = vec![];
+            write_presets(&presets, format, &mut file).unwrap();
+            let text = String::from_utf8(file).unwrap();
+            assert_eq!(read_presets(&text, format).unwrap(), presets);
+        }
+
+        let text = "# Inputs that broke someone\n\nfirst: 2 1 0\n  second  :  5   -4\n";
+        assert_eq!(
+            read_presets(text, PresetFormat::Text).unwrap(),
+            [preset("first", &[2, 1, 0]), preset("second", &[5, -4])]
+        );
+        assert!(matches!(
+            read_presets("a: 1\n2 3\n", PresetFormat::Text),
+            Err(PresetError::MissingName(2))
+        ));
+        assert!(matches!(
+            read_presets("a: 1\nb: 2 x\n", PresetFormat::Text),
+            Err(PresetError::Parse { line: 2, .. })
+        ));
+        assert!(read_presets("a: 1", PresetFormat::Json).is_err());
+
+        let mut merged = vec![preset("a", &[1]), preset("b", &[2])];
+        merge_presets(&mut merged, vec![preset("b", &[3]), preset("c", &[4])]);
+        assert_eq!(
+            merged,
+            [preset("a", &[1]), preset("b", &[3]), preset("c", &[4])]
+        );
+    }
+}
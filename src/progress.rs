@@ -0,0 +1,282 @@
+//! Progress metrics of a run, computed at every program counter.
+//!
+//! The disorder is the one of [`crate::numbers::compute_disorder`], measured on
+//! stack A alone. Every metric is updated incrementally, in `O(log n)` per
+//! instruction.
+
+use std::collections::{BTreeSet, VecDeque};
+
+use crate::{
+    fenwick::Fenwick,
+    sim::{PushSwapInstruction, PushSwapSim},
+};
+
+/// State of the stacks after an instruction was executed.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ProgressStep {
+    /// Ratio of pairs of numbers of stack A that are in the wrong order, from 0
+    /// (sorted) to 1 (reverse sorted), like [`crate::numbers::compute_disorder`].
+    pub disorder: f64,
+    /// Length of the sorted run at the top of stack A.
+    pub sorted_prefix: usize,
+    /// Amount of numbers in stack A.
+    pub size_a: usize,
+    /// Amount of numbers in stack B.
+    pub size_b: usize,
+}
+
+/// Stack A, along with the positions of its descents to find its sorted run.
+/// Positions are keys that stay valid when numbers are added or removed at
+/// either end: the number at index `i` has the key `head + i`.
+struct StackA {
+    numbers: VecDeque<u32>,
+    values: Fenwick,
+    head: i64,
+    /// Keys of the numbers greater than the number right after them.
+    descents: BTreeSet<i64>,
+}
+
+impl StackA {
+    fn key(&self, index: usize) -> i64 {
+        self.head + index as i64
+    }
+
+    fn push_front(&mut self, n: u32) {
+        self.head -= 1;
+        if self.numbers.front().is_some_and(|first| n > *first) {
+            self.descents.insert(self.head);
+        }
+        self.numbers.push_front(n);
+        self.values.add(n, 1);
+    }
+
+    fn pop_front(&mut self) -> u32 {
+        let n = self.numbers.pop_front().expect("stack A is empty");
+        self.descents.remove(&self.head);
+        self.head += 1;
+        self.values.add(n, -1);
+        n
+    }
+
+    fn push_back(&mut self, n: u32) {
+        if self.numbers.back().is_some_and(|last| *last > n) {
+            self.descents.insert(self.key(self.numbers.len() - 1));
+        }
+        self.numbers.push_back(n);
+        self.values.add(n, 1);
+    }
+
+    fn pop_back(&mut self) -> u32 {
+        let n = self.numbers.pop_back().expect("stack A is empty");
+        if !self.numbers.is_empty() {
+            self.descents.remove(&self.key(self.numbers.len() - 1));
+        }
+        self.values.add(n, -1);
+        n
+    }
+
+    fn sorted_prefix(&self) -> usize {
+        match self.descents.range(self.head..).next() {
+            Some(key) => (key - self.head + 1) as usize,
+            None => self.numbers.len(),
+        }
+    }
+}
+
+struct Tracker {
+    a: StackA,
+    b: VecDeque<u32>,
+    /// Inversions of stack A alone.
+    inversions: i64,
+}
+
+impl Tracker {
+    fn new(numbers: &[u32]) -> Self {
+        let mut a = StackA {
+            numbers: VecDeque::new(),
+            values: Fenwick::new(numbers.len()),
+            head: 0,
+            descents: BTreeSet::new(),
+        };
+        let mut inversions = 0;
+        for &n in numbers.iter().rev() {
+            inversions += a.values.below(n) as i64;
+            a.push_front(n);
+        }
+        Self {
+            a,
+            b: VecDeque::new(),
+            inversions,
+        }
+    }
+
+    fn step(&self) -> ProgressStep {
+        let len = self.a.numbers.len() as f64;
+        let pairs = len * (len - 1.) / 2.;
+        ProgressStep {
+            disorder: if pairs > 0. {
+                self.inversions as f64 / pairs
+            } else {
+                0.
+            },
+            sorted_prefix: self.a.sorted_prefix(),
+            size_a: self.a.numbers.len(),
+            size_b: self.b.len(),
+        }
+    }
+
+    /// Change in inversions when moving a number from before the `others` numbers
+    /// of stack A to after them, `below` of them being smaller than the number.
+    fn moved_after(below: usize, others: usize) -> i64 {
+        (others - below) as i64 - below as i64
+    }
+
+    fn swap_a(&mut self) {
+        if self.a.numbers.len() < 2 {
+            return;
+        }
+        let first = self.a.pop_front();
+        let second = self.a.pop_front();
+        self.inversions += if first > second { -1 } else { 1 };
+        self.a.push_front(first);
+        self.a.push_front(second);
+    }
+
+    fn swap_b(&mut self) {
+        if self.b.len() >= 2 {
+            self.b.swap(0, 1);
+        }
+    }
+
+    fn push_a(&mut self) {
+        if let Some(n) = self.b.pop_front() {
+            // The number comes before every number of stack A, so every smaller one is an inversion
+            self.inversions += self.a.values.below(n) as i64;
+            self.a.push_front(n);
+        }
+    }
+
+    fn push_b(&mut self) {
+        if !self.a.numbers.is_empty() {
+            let n = self.a.pop_front();
+            self.inversions -= self.a.values.below(n) as i64;
+            self.b.push_front(n);
+        }
+    }
+
+    fn rotate_a(&mut self) {
+        if self.a.numbers.len() < 2 {
+            return;
+        }
+        let n = self.a.pop_front();
+        let below = self.a.values.below(n);
+        self.inversions += Self::moved_after(below, self.a.numbers.len());
+        self.a.push_back(n);
+    }
+
+    fn reverse_rotate_a(&mut self) {
+        if self.a.numbers.len() < 2 {
+            return;
+        }
+        let n = self.a.pop_back();
+        let below = self.a.values.below(n);
+        self.inversions -= Self::moved_after(below, self.a.numbers.len());
+        self.a.push_front(n);
+    }
+
+    fn rotate_b(&mut self) {
+        if self.b.len() >= 2 {
+            self.b.rotate_left(1);
+        }
+    }
+
+    fn reverse_rotate_b(&mut self) {
+        if self.b.len() >= 2 {
+            self.b.rotate_right(1);
+        }
+    }
+
+    fn execute(&mut self, ins: PushSwapInstruction) {
+        use PushSwapInstruction::*;
+        match ins {
+            SwapA => self.swap_a(),
+            SwapB => self.swap_b(),
+            SwapBoth => {
+                self.swap_a();
+                self.swap_b();
+            }
+            PushA => self.push_a(),
+            PushB => self.push_b(),
+            RotateA => self.rotate_a(),
+            RotateB => self.rotate_b(),
+            RotateBoth => {
+                self.rotate_a();
+                self.rotate_b();
+            }
+            ReverseRotateA => self.reverse_rotate_a(),
+            ReverseRotateB => self.reverse_rotate_b(),
+            ReverseRotateBoth => {
+                self.reverse_rotate_a();
+                self.reverse_rotate_b();
+            }
+        }
+    }
+}
+
+/// Computes the progress metrics before the first instruction and after every
+/// instruction of the simulation, regardless of its current program counter.
+pub fn progress(sim: &PushSwapSim) -> Vec<ProgressStep> {
+    let mut tracker = Tracker::new(sim.initial_numbers());
+    let mut steps = Vec::with_capacity(sim.instructions().len() + 1);
+    steps.push(tracker.step());
+    for &ins in sim.instructions() {
+        tracker.execute(ins);
+        steps.push(tracker.step());
+    }
+    steps
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{RngExt, SeedableRng, rngs::StdRng, seq::SliceRandom};
+
+    use super::progress;
+    use crate::sim::{PushSwapInstruction, PushSwapSim};
+
+    #[test]
+    fn test_progress_matches_stacks() {
+        let mut rng = StdRng::seed_from_u64(13);
+        for amount in [0, 1, 2, 5, 30] {
+            let mut numbers: Vec<u32> = (0..amount).collect();
+            numbers.shuffle(&mut rng);
+            let instructions: Vec<_> = (0..500)
+                .map(|_| PushSwapInstruction::ALL[rng.random_range(0..11)])
+                .collect();
+            let mut sim = PushSwapSim::default();
+            sim.load_instructions(numbers, instructions);
+            let steps = progress(&sim);
+            for step in steps {
+                sim.make_contiguous();
+                let stack_a = sim.stack_a();
+                let mut inversions = 0;
+                for i in 0..stack_a.len() {
+                    for j in (i + 1)..stack_a.len() {
+                        if stack_a[i] > stack_a[j] {
+                            inversions += 1;
+                        }
+                    }
+                }
+                let pairs = (stack_a.len() * stack_a.len().saturating_sub(1) / 2).max(1);
+                assert!((step.disorder - inversions as f64 / pairs as f64).abs() < 1e-9);
+                let prefix = stack_a
+                    .windows(2)
+                    .position(|w| w[0] > w[1])
+                    .map_or(stack_a.len(), |i| i + 1);
+                assert_eq!(step.sorted_prefix, prefix);
+                assert_eq!(step.size_a, stack_a.len());
+                assert_eq!(step.size_b, sim.stack_b().len());
+                sim.step();
+            }
+        }
+    }
+}
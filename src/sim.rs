@@ -1,82 +1,131 @@
+//! Simulation of the push_swap instructions on stacks A and B.
+
 use std::{collections::VecDeque, fmt::Display};
 
+use crate::parser::{ParseError, parse_push_swap};
+
+/// A stack of normalized numbers, the top of the stack being the front.
 pub type Stack = VecDeque<u32>;
 
+/// An instruction of the push_swap language.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PushSwapInstruction {
+    /// `sa`: swaps the first 2 numbers of stack A.
     SwapA,
+    /// `sb`: swaps the first 2 numbers of stack B.
     SwapB,
+    /// `ss`: `sa` and `sb` at the same time.
     SwapBoth,
+    /// `pa`: moves the first number of stack B to the top of stack A.
     PushA,
+    /// `pb`: moves the first number of stack A to the top of stack B.
     PushB,
+    /// `ra`: shifts stack A up by 1, the first number becomes the last one.
     RotateA,
+    /// `rb`: shifts stack B up by 1, the first number becomes the last one.
     RotateB,
+    /// `rr`: `ra` and `rb` at the same time.
     RotateBoth,
+    /// `rra`: shifts stack A down by 1, the last number becomes the first one.
     ReverseRotateA,
+    /// `rrb`: shifts stack B down by 1, the last number becomes the first one.
     ReverseRotateB,
+    /// `rrr`: `rra` and `rrb` at the same time.
     ReverseRotateBoth,
 }
 
 impl PushSwapInstruction {
-    pub fn execute(&self, stack_a: &mut Stack, stack_b: &mut Stack) {
+    /// Every instruction, in the order they are listed in the subject.
+    pub const ALL: [PushSwapInstruction; 11] = [
+        PushSwapInstruction::SwapA,
+        PushSwapInstruction::SwapB,
+        PushSwapInstruction::SwapBoth,
+        PushSwapInstruction::PushA,
+        PushSwapInstruction::PushB,
+        PushSwapInstruction::RotateA,
+        PushSwapInstruction::RotateB,
+        PushSwapInstruction::RotateBoth,
+        PushSwapInstruction::ReverseRotateA,
+        PushSwapInstruction::ReverseRotateB,
+        PushSwapInstruction::ReverseRotateBoth,
+    ];
+
+    /// Executes the instruction on the given stacks. Returns whether the stacks changed.
+    pub fn execute(&self, stack_a: &mut Stack, stack_b: &mut Stack) -> bool {
         use PushSwapInstruction::*;
         match *self {
             SwapA => {
-                if stack_a.len() >= 2 {
-                    (stack_a[0], stack_a[1]) = (stack_a[1], stack_a[0]);
+                if stack_a.len() < 2 {
+                    return false;
                 }
+                (stack_a[0], stack_a[1]) = (stack_a[1], stack_a[0]);
             }
             SwapB => {
-                if stack_b.len() >= 2 {
-                    (stack_b[0], stack_b[1]) = (stack_b[1], stack_b[0]);
+                if stack_b.len() < 2 {
+                    return false;
                 }
+                (stack_b[0], stack_b[1]) = (stack_b[1], stack_b[0]);
             }
             SwapBoth => {
-                SwapA.execute(stack_a, stack_b);
-                SwapB.execute(stack_a, stack_b);
+                let a = SwapA.execute(stack_a, stack_b);
+                let b = SwapB.execute(stack_a, stack_b);
+                return a || b;
             }
             PushA => {
-                if let Some(value) = stack_b.pop_front() {
-                    stack_a.push_front(value);
-                }
+                let Some(value) = stack_b.pop_front() else {
+                    return false;
+                };
+                stack_a.push_front(value);
             }
             PushB => {
-                if let Some(value) = stack_a.pop_front() {
-                    stack_b.push_front(value);
-                }
+                let Some(value) = stack_a.pop_front() else {
+                    return false;
+                };
+                stack_b.push_front(value);
             }
             RotateA => {
-                if let Some(value) = stack_a.pop_front() {
-                    stack_a.push_back(value);
+                if stack_a.len() < 2 {
+                    return false;
                 }
+                stack_a.rotate_left(1);
             }
             RotateB => {
-                if let Some(value) = stack_b.pop_front() {
-                    stack_b.push_back(value);
+                if stack_b.len() < 2 {
+                    return false;
                 }
+                stack_b.rotate_left(1);
             }
             RotateBoth => {
-                RotateA.execute(stack_a, stack_b);
-                RotateB.execute(stack_a, stack_b);
+                let a = RotateA.execute(stack_a, stack_b);
+                let b = RotateB.execute(stack_a, stack_b);
+                return a || b;
             }
             ReverseRotateA => {
-                if let Some(value) = stack_a.pop_back() {
-                    stack_a.push_front(value);
+                if stack_a.len() < 2 {
+                    return false;
                 }
+                stack_a.rotate_right(1);
             }
             ReverseRotateB => {
-                if let Some(value) = stack_b.pop_back() {
-                    stack_b.push_front(value);
+                if stack_b.len() < 2 {
+                    return false;
                 }
+                stack_b.rotate_right(1);
             }
             ReverseRotateBoth => {
-                ReverseRotateA.execute(stack_a, stack_b);
-                ReverseRotateB.execute(stack_a, stack_b);
+                let a = ReverseRotateA.execute(stack_a, stack_b);
+                let b = ReverseRotateB.execute(stack_a, stack_b);
+                return a || b;
             }
         }
+        true
     }
 
-    pub fn undo(&self, stack_a: &mut Stack, stack_b: &mut Stack) {
+    /// Reverts the instruction on the given stacks.
+    ///
+    /// This is only exact if the instruction changed the stacks when it was executed:
+    /// undoing a `pa` that ran on an empty stack B would move a number out of stack A.
+    pub fn undo(&self, stack_a: &mut Stack, stack_b: &mut Stack) -> bool {
         use PushSwapInstruction::*;
         match *self {
             SwapA => SwapA.execute(stack_a, stack_b),
@@ -114,15 +163,55 @@ impl Display for PushSwapInstruction {
     }
 }
 
-#[derive(Debug, Default)]
+/// Default amount of memory that can be used to store keyframes, in bytes.
+pub const DEFAULT_KEYFRAME_MEMORY: usize = 16 * 1024 * 1024;
+
+/// Keyframes are never recorded closer than this amount of instructions.
+const MIN_KEYFRAME_INTERVAL: usize = 64;
+
+/// Simulates a push_swap program on stacks A and B, one instruction at a time.
+///
+/// Instructions can be stepped through in both directions, and seeking to any
+/// position is cheap thanks to periodic snapshots of the stacks.
+#[derive(Debug)]
 pub struct PushSwapSim {
     instructions: Vec<PushSwapInstruction>,
+    /// For every instruction, whether it changed the stacks when executed.
+    effective: Vec<bool>,
     program_counter: usize,
+    initial: Vec<u32>,
+    /// The numbers before normalization, sorted, so that each one is at the index of its rank.
+    originals: Vec<i64>,
     stack_a: Stack,
     stack_b: Stack,
+    /// Snapshots of both stacks, taken every `keyframe_interval` instructions.
+    keyframes: Vec<(Stack, Stack)>,
+    keyframe_interval: usize,
+    keyframe_memory: usize,
+}
+
+impl Default for PushSwapSim {
+    fn default() -> Self {
+        Self {
+            instructions: vec![],
+            effective: vec![],
+            program_counter: 0,
+            initial: vec![],
+            originals: vec![],
+            stack_a: Stack::new(),
+            stack_b: Stack::new(),
+            keyframes: vec![],
+            keyframe_interval: MIN_KEYFRAME_INTERVAL,
+            keyframe_memory: DEFAULT_KEYFRAME_MEMORY,
+        }
+    }
 }
 
-fn normalized_vec(numbers: &[i64]) -> Vec<u32> {
+/// Replaces every number with its rank among the numbers, starting at 0.
+/// Equal numbers get consecutive ranks in order of appearance, since push_swap
+/// rejects them (see [`crate::numbers::check_numbers`]).
+#[allow(clippy::unnecessary_sort_by)]
+pub fn normalized_vec(numbers: &[i64]) -> Vec<u32> {
     let mut numbers: Vec<_> = numbers.iter().enumerate().collect();
     numbers.sort_by(|(_, i1), (_, i2)| i1.cmp(i2));
     let mut numbers: Vec<_> = numbers
@@ -134,72 +223,163 @@ fn normalized_vec(numbers: &[i64]) -> Vec<u32> {
     numbers.into_iter().map(|(i, _)| i as u32).collect()
 }
 
-pub fn parse_push_swap(text: &str) -> Result<Vec<PushSwapInstruction>, usize> {
-    let raw = text.split_whitespace().enumerate();
-    let mut instructions = vec![];
-    for (i, ins) in raw {
-        let i = i + 1;
-        let ins = {
-            use PushSwapInstruction::*;
-            match ins {
-                "sa" => SwapA,
-                "sb" => SwapB,
-                "ss" => SwapBoth,
-                "pa" => PushA,
-                "pb" => PushB,
-                "ra" => RotateA,
-                "rb" => RotateB,
-                "rr" => RotateBoth,
-                "rra" => ReverseRotateA,
-                "rrb" => ReverseRotateB,
-                "rrr" => ReverseRotateBoth,
-                _ => return Err(i),
-            }
-        };
-        instructions.push(ins);
+impl PushSwapSim {
+    /// Loads normalized numbers (see [`normalized_vec`]) and parses the instructions
+    /// to execute on them, resetting the program counter.
+    pub fn load_normalized(&mut self, numbers: Vec<u32>, text: &str) -> Result<(), ParseError> {
+        let instructions = parse_push_swap(text)?;
+        self.load_instructions(numbers, instructions);
+        Ok(())
     }
-    Ok(instructions)
-}
 
-impl PushSwapSim {
-    pub fn load_normalized(&mut self, numbers: Vec<u32>, text: &str) -> Result<(), usize> {
-        self.instructions = parse_push_swap(text)?;
+    /// Loads normalized numbers and already parsed instructions, resetting the program counter.
+    pub fn load_instructions(&mut self, numbers: Vec<u32>, instructions: Vec<PushSwapInstruction>) {
+        self.instructions = instructions;
         self.program_counter = 0;
-        self.stack_a = VecDeque::from(numbers);
+        self.stack_a = VecDeque::from(numbers.clone());
         self.stack_b = VecDeque::new();
-        Ok(())
+        self.initial = numbers;
+        self.originals.clear();
+        self.compute_effects();
+        self.reset_keyframes();
     }
 
-    pub fn load_random(&mut self, numbers: &[i64], text: &str) -> Result<(), usize> {
-        let numbers = normalized_vec(numbers);
-        self.load_normalized(numbers, text)
+    /// Replaces the instructions while keeping the numbers, resetting the program counter.
+    pub fn replace_instructions(&mut self, instructions: Vec<PushSwapInstruction>) {
+        self.instructions = instructions;
+        self.program_counter = 0;
+        self.stack_a = VecDeque::from(self.initial.clone());
+        self.stack_b = VecDeque::new();
+        self.compute_effects();
+        self.reset_keyframes();
     }
 
+    fn compute_effects(&mut self) {
+        let mut stack_a = self.stack_a.clone();
+        let mut stack_b = self.stack_b.clone();
+        self.effective = self
+            .instructions
+            .iter()
+            .map(|ins| ins.execute(&mut stack_a, &mut stack_b))
+            .collect();
+    }
+
+    /// Sets the amount of memory (in bytes) that can be used to store keyframes
+    /// for seeking. Keyframes are recorded again with the new interval.
+    pub fn set_keyframe_memory(&mut self, bytes: usize) {
+        self.keyframe_memory = bytes;
+        self.reset_keyframes();
+    }
+
+    fn reset_keyframes(&mut self) {
+        let keyframe_size = (self.initial.len() * size_of::<u32>()).max(1);
+        let max_keyframes = (self.keyframe_memory / keyframe_size).max(1);
+        self.keyframe_interval = self
+            .instructions
+            .len()
+            .saturating_add(1)
+            .div_ceil(max_keyframes)
+            .max(MIN_KEYFRAME_INTERVAL);
+        // Every keyframe is recorded upfront, so that the first seek to any
+        // position is as fast as the next ones
+        let mut stack_a = VecDeque::from(self.initial.clone());
+        let mut stack_b = VecDeque::new();
+        self.keyframes.clear();
+        self.keyframes.push((stack_a.clone(), stack_b.clone()));
+        for (i, ins) in self.instructions.iter().enumerate() {
+            ins.execute(&mut stack_a, &mut stack_b);
+            if (i + 1) % self.keyframe_interval == 0 {
+                self.keyframes.push((stack_a.clone(), stack_b.clone()));
+            }
+        }
+    }
+
+    /// Normalizes the numbers, then loads them along with the parsed instructions.
+    pub fn load_random(&mut self, numbers: &[i64], text: &str) -> Result<(), ParseError> {
+        let normalized = normalized_vec(numbers);
+        self.load_normalized(normalized, text)?;
+        self.originals = numbers.to_vec();
+        self.originals.sort();
+        Ok(())
+    }
+
+    /// Rearranges the stacks in memory so that [`Self::stack_a`] and [`Self::stack_b`]
+    /// return every number.
     pub fn make_contiguous(&mut self) {
         let _ = self.stack_a.make_contiguous();
         let _ = self.stack_b.make_contiguous();
     }
 
+    /// Stack A, top first. Only complete after calling [`Self::make_contiguous`].
     pub fn stack_a(&self) -> &[u32] {
         self.stack_a.as_slices().0
     }
 
+    /// Stack B, top first. Only complete after calling [`Self::make_contiguous`].
     pub fn stack_b(&self) -> &[u32] {
         self.stack_b.as_slices().0
     }
 
+    /// Both stacks, top first, without having to call [`Self::make_contiguous`].
+    pub fn stacks(&self) -> (&Stack, &Stack) {
+        (&self.stack_a, &self.stack_b)
+    }
+
+    /// The normalized numbers stack A started with.
+    pub fn initial_numbers(&self) -> &[u32] {
+        &self.initial
+    }
+
+    /// The number a normalized number was ranked from, if the numbers were
+    /// loaded with [`Self::load_random`].
+    pub fn original_value(&self, number: u32) -> Option<i64> {
+        self.originals.get(number as usize).copied()
+    }
+
+    /// The numbers before normalization, sorted so that each one is at the index
+    /// of its normalized number. Empty unless loaded with [`Self::load_random`].
+    pub fn original_values(&self) -> &[i64] {
+        &self.originals
+    }
+
+    /// Total amount of numbers in both stacks.
     pub fn amount(&self) -> usize {
         self.stack_a.len() + self.stack_b.len()
     }
 
+    /// Returns whether stack A is sorted and stack B is empty.
+    pub fn is_sorted(&self) -> bool {
+        self.stack_a.iter().is_sorted() && self.stack_b.is_empty()
+    }
+
+    /// Amount of instructions executed so far.
     pub fn program_counter(&self) -> usize {
         self.program_counter
     }
 
+    /// Every loaded instruction.
     pub fn instructions(&self) -> &[PushSwapInstruction] {
         &self.instructions
     }
 
+    /// Returns whether the instruction at the given index has no effect on the stacks,
+    /// `false` if there is no instruction at this index.
+    pub fn is_no_op(&self, index: usize) -> bool {
+        self.effective
+            .get(index)
+            .is_some_and(|effective| !effective)
+    }
+
+    /// Returns the indices of all instructions that have no effect on the stacks.
+    pub fn no_ops(&self) -> Vec<usize> {
+        self.effective
+            .iter()
+            .enumerate()
+            .filter_map(|(i, effective)| (!effective).then_some(i))
+            .collect()
+    }
+
+    /// Executes the next instruction. Returns `false` if every instruction was executed.
     pub fn step(&mut self) -> bool {
         if self.program_counter >= self.instructions.len() {
             return false;
@@ -209,38 +389,60 @@ impl PushSwapSim {
         true
     }
 
+    /// Reverts the last executed instruction. Returns `false` if none was executed.
     pub fn undo(&mut self) -> bool {
         if self.program_counter == 0 {
             return false;
         }
         self.program_counter -= 1;
-        self.instructions[self.program_counter].undo(&mut self.stack_a, &mut self.stack_b);
+        if self.effective[self.program_counter] {
+            self.instructions[self.program_counter].undo(&mut self.stack_a, &mut self.stack_b);
+        }
         true
     }
 
+    /// Moves the program counter to the given position, restoring the closest
+    /// keyframe if replaying from it is cheaper than stepping from the current state.
     pub fn skip_to(&mut self, counter: usize) -> bool {
-        let mut needs_redraw = false;
         let counter = counter.clamp(0, self.instructions.len());
+        if counter == self.program_counter || self.keyframes.is_empty() {
+            return false;
+        }
+        let keyframe = (counter / self.keyframe_interval).min(self.keyframes.len() - 1);
+        let keyframe_counter = keyframe * self.keyframe_interval;
+        if counter - keyframe_counter < counter.abs_diff(self.program_counter) {
+            let (stack_a, stack_b) = &self.keyframes[keyframe];
+            self.stack_a.clone_from(stack_a);
+            self.stack_b.clone_from(stack_b);
+            self.program_counter = keyframe_counter;
+        }
         while self.program_counter < counter {
-            needs_redraw |= self.step();
+            self.step();
         }
         while self.program_counter > counter {
-            needs_redraw |= self.undo();
+            self.undo();
         }
-        needs_redraw
+        true
     }
 
+    /// Unloads the numbers and the instructions.
     pub fn clear(&mut self) {
         self.instructions.clear();
+        self.effective.clear();
+        self.initial.clear();
+        self.originals.clear();
         self.stack_a.clear();
         self.stack_b.clear();
+        self.keyframes.clear();
         self.program_counter = 0;
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::normalized_vec;
+    use std::collections::VecDeque;
+
+    use super::{PushSwapInstruction, PushSwapSim, normalized_vec};
 
     #[test]
     fn test_normalizer() {
@@ -250,4 +452,76 @@ mod test {
         let res = normalized_vec(chaotic);
         assert_eq!(res.as_slice(), expected);
     }
+
+    #[test]
+    fn test_skip_to_keyframes() {
+        use PushSwapInstruction::*;
+        let numbers: Vec<u32> = (0..50).rev().collect();
+        let mut instructions = vec![];
+        let (mut len_a, mut len_b) = (numbers.len(), 0);
+        for i in 0..5000 {
+            let ins = match i % 7 {
+                0 if len_a > 0 => PushB,
+                3 if len_b > 0 => PushA,
+                1 | 4 => RotateA,
+                2 => ReverseRotateBoth,
+                5 => SwapBoth,
+                _ => RotateB,
+            };
+            match ins {
+                PushA => (len_a, len_b) = (len_a + 1, len_b - 1),
+                PushB => (len_a, len_b) = (len_a - 1, len_b + 1),
+                _ => {}
+            }
+            instructions.push(ins);
+        }
+        let mut sim = PushSwapSim::default();
+        sim.load_instructions(numbers.clone(), instructions.clone());
+        sim.set_keyframe_memory(numbers.len() * 4 * 10);
+        for target in [4000, 10, 2500, 2499, 4999, 0, 5000, 1234, 3000] {
+            let mut expected = PushSwapSim::default();
+            expected.load_instructions(numbers.clone(), instructions.clone());
+            for _ in 0..target {
+                expected.step();
+            }
+            sim.skip_to(target);
+            assert_eq!(sim.program_counter(), target);
+            assert_eq!(sim.stack_a, expected.stack_a);
+            assert_eq!(sim.stack_b, expected.stack_b);
+        }
+        assert!(sim.keyframes.len() <= 10);
+    }
+
+    #[test]
+    fn test_seek_after_stepping() {
+        let numbers: Vec<u32> = (0..20).rev().collect();
+        let instructions = [PushSwapInstruction::RotateA, PushSwapInstruction::SwapA].repeat(3000);
+        let mut sim = PushSwapSim::default();
+        sim.load_instructions(numbers, instructions);
+        let interval = sim.keyframe_interval;
+        assert_eq!(sim.keyframes.len(), 6000 / interval + 1);
+        for _ in 0..1000 {
+            sim.step();
+        }
+        // Tampering with a keyframe shows whether seeking restores it or steps all the way
+        let target = 4000 / interval * interval;
+        sim.keyframes[target / interval].0 = VecDeque::from([42]);
+        sim.skip_to(target);
+        assert_eq!(sim.stack_a, [42]);
+    }
+
+    #[test]
+    fn test_undo_no_ops() {
+        let mut sim = PushSwapSim::default();
+        sim.load_normalized(vec![2, 0, 1], "pa rb pb sb pb sa rr pa pa pa pa")
+            .unwrap();
+        assert_eq!(sim.no_ops(), &[0, 1, 3, 5, 9, 10]);
+        assert!(sim.is_no_op(10));
+        assert!(!sim.is_no_op(11));
+        sim.skip_to(11);
+        assert!(sim.stack_b.is_empty());
+        while sim.undo() {}
+        assert_eq!(sim.stack_a, [2, 0, 1]);
+        assert!(sim.stack_b.is_empty());
+    }
 }
@@ -0,0 +1,308 @@
+//! Built-in reference sorting algorithms.
+
+use std::fmt::Display;
+
+use crate::{
+    oracle::optimal_solution,
+    sim::{PushSwapInstruction, Stack, normalized_vec},
+};
+
+/// Inputs this small are solved optimally by every solver.
+const OPTIMAL_MAX_NUMBERS: usize = 5;
+
+/// Reference sorting algorithms, used as a baseline to compare push_swap programs with.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Solver {
+    /// Radix sort on the binary representation of the numbers.
+    Radix,
+    /// Chunk sort, pushing ranges of close values.
+    Chunk,
+    /// Cost-based algorithm, also known as the Turk algorithm.
+    #[default]
+    Turk,
+}
+
+impl Solver {
+    /// Every built-in solver.
+    pub const ALL: [Solver; 3] = [Solver::Radix, Solver::Chunk, Solver::Turk];
+
+    /// Short explanation of how the solver works.
+    pub fn description(self) -> &'static str {
+        match self {
+            Solver::Radix => {
+                "Sorts numbers bit by bit, pushing numbers with an unset bit to stack B."
+            }
+            Solver::Chunk => {
+                "Pushes numbers to stack B by chunks of close values, then pushes them back from the largest to the smallest."
+            }
+            Solver::Turk => {
+                "Pushes numbers to stack B in descending order, always picking the number that is the cheapest to move, then pushes them back."
+            }
+        }
+    }
+
+    /// Generates instructions that sort the numbers. Up to 5 numbers are sorted optimally.
+    pub fn solve(self, numbers: &[i64]) -> Vec<PushSwapInstruction> {
+        let numbers = normalized_vec(numbers);
+        if numbers.is_sorted() {
+            return vec![];
+        }
+        if numbers.len() <= OPTIMAL_MAX_NUMBERS
+            && let Some(solution) = optimal_solution(&numbers)
+        {
+            return solution;
+        }
+        let mut stacks = Stacks::new(numbers);
+        match self {
+            Solver::Radix => radix(&mut stacks),
+            Solver::Chunk => chunk(&mut stacks),
+            Solver::Turk => turk(&mut stacks),
+        }
+        stacks.instructions
+    }
+}
+
+impl Display for Solver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match *self {
+            Solver::Radix => "Radix Sort",
+            Solver::Chunk => "Chunk Sort",
+            Solver::Turk => "Turk Algorithm",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Stacks that record every instruction executed on them.
+struct Stacks {
+    a: Stack,
+    b: Stack,
+    instructions: Vec<PushSwapInstruction>,
+}
+
+impl Stacks {
+    fn new(numbers: Vec<u32>) -> Self {
+        Self {
+            a: numbers.into(),
+            b: Stack::new(),
+            instructions: vec![],
+        }
+    }
+
+    fn run(&mut self, ins: PushSwapInstruction) {
+        ins.execute(&mut self.a, &mut self.b);
+        self.instructions.push(ins);
+    }
+
+    fn repeat(&mut self, ins: PushSwapInstruction, times: usize) {
+        for _ in 0..times {
+            self.run(ins);
+        }
+    }
+}
+
+fn position_of(stack: &Stack, value: u32) -> usize {
+    stack
+        .iter()
+        .position(|n| *n == value)
+        .expect("value not in stack")
+}
+
+/// Brings the number at the given index to the top of a stack, in the cheapest direction.
+fn rotate_to_top(stacks: &mut Stacks, index: usize, on_a: bool) {
+    use PushSwapInstruction::*;
+    let len = if on_a { stacks.a.len() } else { stacks.b.len() };
+    let (up, down) = if on_a {
+        (RotateA, ReverseRotateA)
+    } else {
+        (RotateB, ReverseRotateB)
+    };
+    if index <= len / 2 {
+        stacks.repeat(up, index);
+    } else {
+        stacks.repeat(down, len - index);
+    }
+}
+
+fn sort_three(stacks: &mut Stacks) {
+    use PushSwapInstruction::*;
+    let Some(&max) = stacks.a.iter().max() else {
+        return;
+    };
+    if stacks.a.len() == 3 {
+        if stacks.a[0] == max {
+            stacks.run(RotateA);
+        } else if stacks.a[1] == max {
+            stacks.run(ReverseRotateA);
+        }
+    }
+    if stacks.a.len() >= 2 && stacks.a[0] > stacks.a[1] {
+        stacks.run(SwapA);
+    }
+}
+
+fn radix(stacks: &mut Stacks) {
+    use PushSwapInstruction::*;
+    let amount = stacks.a.len();
+    let bits = u32::BITS - (amount as u32 - 1).leading_zeros();
+    for bit in 0..bits {
+        for _ in 0..amount {
+            if stacks.a[0] & (1 << bit) == 0 {
+                stacks.run(PushB);
+            } else {
+                stacks.run(RotateA);
+            }
+        }
+        let pushed = stacks.b.len();
+        stacks.repeat(PushA, pushed);
+    }
+}
+
+fn push_back_largest(stacks: &mut Stacks) {
+    while let Some(&max) = stacks.b.iter().max() {
+        let index = position_of(&stacks.b, max);
+        rotate_to_top(stacks, index, false);
+        stacks.run(PushSwapInstruction::PushA);
+    }
+}
+
+fn chunk(stacks: &mut Stacks) {
+    use PushSwapInstruction::*;
+    let range = ((stacks.a.len() as f64).sqrt() * 1.4) as u32;
+    let mut pushed = 0;
+    while let Some(&top) = stacks.a.front() {
+        if top <= pushed {
+            stacks.run(PushB);
+            stacks.run(RotateB);
+            pushed += 1;
+        } else if top <= pushed + range {
+            stacks.run(PushB);
+            pushed += 1;
+        } else {
+            stacks.run(RotateA);
+        }
+    }
+    push_back_largest(stacks);
+}
+
+/// Index of the number `value` should be placed on top of in stack B, so that
+/// stack B stays sorted in descending order.
+fn target_in_b(b: &Stack, value: u32) -> usize {
+    let target = b
+        .iter()
+        .filter(|n| **n < value)
+        .max()
+        .or_else(|| b.iter().max())
+        .expect("stack B is empty");
+    position_of(b, *target)
+}
+
+/// Index of the number `value` should be placed under in stack A, so that
+/// stack A stays sorted in ascending order.
+fn target_in_a(a: &Stack, value: u32) -> usize {
+    let target = a
+        .iter()
+        .filter(|n| **n > value)
+        .min()
+        .or_else(|| a.iter().min())
+        .expect("stack A is empty");
+    position_of(a, *target)
+}
+
+/// Brings the numbers at the given indices to the top of both stacks, with the
+/// cheapest combination of rotations.
+fn rotate_both_to_top(stacks: &mut Stacks, index_a: usize, index_b: usize) {
+    use PushSwapInstruction::*;
+    let (up_a, down_a) = (index_a, stacks.a.len() - index_a);
+    let (up_b, down_b) = (index_b, stacks.b.len() - index_b);
+    let (together_up, together_down) = (up_a.max(up_b), down_a.max(down_b));
+    let cheapest = together_up
+        .min(together_down)
+        .min(up_a + down_b)
+        .min(down_a + up_b);
+    if cheapest == together_up {
+        let both = up_a.min(up_b);
+        stacks.repeat(RotateBoth, both);
+        stacks.repeat(RotateA, up_a - both);
+        stacks.repeat(RotateB, up_b - both);
+    } else if cheapest == together_down {
+        let both = down_a.min(down_b);
+        stacks.repeat(ReverseRotateBoth, both);
+        stacks.repeat(ReverseRotateA, down_a - both);
+        stacks.repeat(ReverseRotateB, down_b - both);
+    } else if cheapest == up_a + down_b {
+        stacks.repeat(RotateA, up_a);
+        stacks.repeat(ReverseRotateB, down_b);
+    } else {
+        stacks.repeat(ReverseRotateA, down_a);
+        stacks.repeat(RotateB, up_b);
+    }
+}
+
+fn rotation_cost(index_a: usize, len_a: usize, index_b: usize, len_b: usize) -> usize {
+    let (up_a, down_a) = (index_a, len_a - index_a);
+    let (up_b, down_b) = (index_b, len_b - index_b);
+    up_a.max(up_b)
+        .min(down_a.max(down_b))
+        .min(up_a + down_b)
+        .min(down_a + up_b)
+}
+
+fn turk(stacks: &mut Stacks) {
+    use PushSwapInstruction::*;
+    let pushes = stacks.a.len().saturating_sub(3).min(2);
+    stacks.repeat(PushB, pushes);
+    while stacks.a.len() > 3 {
+        let (len_a, len_b) = (stacks.a.len(), stacks.b.len());
+        let (index_a, index_b) = stacks
+            .a
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (i, target_in_b(&stacks.b, *n)))
+            .min_by_key(|(i, j)| rotation_cost(*i, len_a, *j, len_b))
+            .expect("stack A is empty");
+        rotate_both_to_top(stacks, index_a, index_b);
+        stacks.run(PushB);
+    }
+    sort_three(stacks);
+    while let Some(&top) = stacks.b.front() {
+        let index = target_in_a(&stacks.a, top);
+        rotate_to_top(stacks, index, true);
+        stacks.run(PushA);
+    }
+    let min = *stacks.a.iter().min().expect("stack A is empty");
+    let index = position_of(&stacks.a, min);
+    rotate_to_top(stacks, index, true);
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+
+    use super::Solver;
+    use crate::{parser::format_instructions, sim::PushSwapSim};
+
+    #[test]
+    fn solvers_sort() {
+        let mut rng = StdRng::seed_from_u64(9);
+        for solver in Solver::ALL {
+            for amount in [0, 1, 2, 3, 4, 6, 7, 10, 100, 500] {
+                let mut numbers: Vec<i64> = (0..amount).map(|n| n * 3 - 50).collect();
+                numbers.shuffle(&mut rng);
+                let instructions = solver.solve(&numbers);
+                let mut sim = PushSwapSim::default();
+                sim.load_random(&numbers, &format_instructions(&instructions))
+                    .unwrap();
+                while sim.step() {}
+                sim.make_contiguous();
+                assert!(
+                    sim.stack_a().is_sorted(),
+                    "{} failed on {:?}",
+                    solver,
+                    numbers
+                );
+                assert_eq!(sim.stack_a().len(), amount as usize);
+            }
+        }
+    }
+}
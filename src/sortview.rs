@@ -2,13 +2,19 @@ use std::time::{Duration, Instant};
 
 use cgmath::{Matrix4, SquareMatrix};
 
+use psv::sim::PushSwapSim;
+
 use crate::{
     config::Config,
     gui::{LoadingOptions, PlaybackControls, VisualOptions},
-    sim::PushSwapSim,
     vertex::{Vertex, VertexIndexPair},
 };
 
+/// Horizontal space between the compared simulations, relative to the width of a stack.
+const COMPARISON_GAP: f32 = 0.1;
+/// Minimum length of a bar drawn proportionally to its original value, relative to the width of a stack.
+const MIN_PROPORTIONAL_BAR: f32 = 0.005;
+
 pub struct SortView {
     config: Config,
     projection: Matrix4<f32>,
@@ -20,6 +26,7 @@ pub struct SortView {
     show_playback: bool,
     playback: PlaybackControls,
     sim: PushSwapSim,
+    comparison: Option<PushSwapSim>,
     playing_sim: bool,
     last_instant: Instant,
     exec_interval: Duration,
@@ -39,6 +46,7 @@ impl SortView {
             show_playback: false,
             playback: PlaybackControls::default(),
             sim: Default::default(),
+            comparison: None,
             playing_sim: false,
             last_instant: Instant::now(),
             exec_interval: Duration::from_secs_f64(1. / 60.),
@@ -54,18 +62,131 @@ impl SortView {
     pub fn get_tris_data(&mut self) -> Option<VertexIndexPair> {
         if self.regenerate_render_data {
             self.regenerate_render_data = false;
-            self.sim.make_contiguous();
-            let stack_a = self.sim.stack_a();
-            let stack_b = self.sim.stack_b();
-            let num_range = stack_a.len() as u32 + stack_b.len() as u32;
-            let mut data = self.generate_tris_data(num_range, stack_a, false);
-            data.extend(self.generate_tris_data(num_range, stack_b, true));
+            let mut data = self.generate_sim_tris_data(&self.sim, 0.);
+            if let Some(comparison) = &self.comparison {
+                let offset = self.sim.amount() as f32 * (2. + COMPARISON_GAP);
+                data.extend(self.generate_sim_tris_data(comparison, offset));
+            }
             Some(data)
         } else {
             None
         }
     }
 
+    /// Width of the sort view, in the units of the projection.
+    fn view_width(&self) -> f32 {
+        let num_range = self.sim.amount() as f32;
+        if self.comparison.is_some() {
+            num_range * (4. + COMPARISON_GAP)
+        } else {
+            num_range * 2.
+        }
+    }
+
+    fn update_projection(&mut self) {
+        let num_range = self.sim.amount() as f32;
+        if num_range == 0. {
+            return;
+        }
+        self.projection = cgmath::ortho(0., self.view_width(), num_range, 0., -1., 1.);
+    }
+
+    /// Finds the number drawn on the row under the given position, in the units of the projection.
+    fn number_at(&self, x: f32, y: f32) -> Option<u32> {
+        let num_range = self.sim.amount() as f32;
+        let (sim, x) = if x < num_range * 2. {
+            (&self.sim, x)
+        } else {
+            (
+                self.comparison.as_ref()?,
+                x - num_range * (2. + COMPARISON_GAP),
+            )
+        };
+        if x < 0. || y < 0. {
+            return None;
+        }
+        let (stack_a, stack_b) = sim.stacks();
+        let stack = if x < num_range { stack_a } else { stack_b };
+        stack.get(y as usize).copied()
+    }
+
+    /// Selects the clicked number, and shows where the selected number is along with its moves.
+    fn tracking_ui(&mut self, ctx: &egui::Context) {
+        let rect = ctx.viewport_rect();
+        let num_range = self.sim.amount() as f32;
+        if num_range == 0. {
+            return;
+        }
+        let clicked = ctx.input(|i| {
+            i.pointer
+                .primary_clicked()
+                .then(|| i.pointer.interact_pos())
+        });
+        if let Some(pos) = clicked.flatten()
+            && !ctx.is_pointer_over_area()
+        {
+            let x = (pos.x - rect.min.x) / rect.width() * self.view_width();
+            let y = (pos.y - rect.min.y) / rect.height() * num_range;
+            let number = self
+                .number_at(x, y)
+                .filter(|n| Some(*n) != self.playback.tracked());
+            self.playback.track(&self.sim, number);
+            self.regenerate_render_data = true;
+        }
+        let Some(number) = self.playback.tracked() else {
+            return;
+        };
+        let Some((moves, total)) = self.playback.tracked_moves(&self.sim) else {
+            return;
+        };
+        let (stack_a, stack_b) = self.sim.stacks();
+        let (stack, index, offset) = match stack_a.iter().position(|n| *n == number) {
+            Some(index) => ("A", index, 0.),
+            None => match stack_b.iter().position(|n| *n == number) {
+                Some(index) => ("B", index, num_range),
+                None => return,
+            },
+        };
+        let (_, end) = self.bar_extent(&self.sim, number);
+        let pos = egui::pos2(
+            rect.min.x + (offset + end) / self.view_width() * rect.width() + 8.,
+            rect.min.y + index as f32 / num_range * rect.height(),
+        );
+        egui::Area::new(egui::Id::new("tracked_number"))
+            .order(egui::Order::Tooltip)
+            .fixed_pos(pos)
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    match self.sim.original_value(number) {
+                        Some(original) => {
+                            ui.strong(format!("Number {} (original value: {})", number, original))
+                        }
+                        None => ui.strong(format!("Number {}", number)),
+                    };
+                    ui.label(format!("Stack {}, index {}", stack, index));
+                    ui.label(format!(
+                        "Pushed {} times ({} in the whole run)",
+                        moves.pushes, total.pushes
+                    ));
+                    ui.label(format!(
+                        "Rotated {} times ({} in the whole run)",
+                        moves.rotations, total.rotations
+                    ));
+                    ui.label(format!(
+                        "Swapped {} times ({} in the whole run)",
+                        moves.swaps, total.swaps
+                    ));
+                });
+            });
+    }
+
+    fn sync_comparison(&mut self) {
+        if let Some(comparison) = self.comparison.as_mut() {
+            self.regenerate_render_data |= self.playback.sync_comparison(&self.sim, comparison);
+        }
+    }
+
     pub fn get_projection_matrix(&self) -> Matrix4<f32> {
         self.projection
     }
@@ -74,6 +195,7 @@ impl SortView {
         self.visual.clear_color()
     }
 
+    #[allow(clippy::collapsible_match)]
     pub fn keyboard_input(&mut self, event: &winit::event::KeyEvent) {
         use winit::keyboard::{KeyCode, PhysicalKey::Code};
         if !event.state.is_pressed() {
@@ -100,32 +222,83 @@ impl SortView {
             }
             _ => {}
         }
+        self.sync_comparison();
     }
 
-    fn generate_tris_data(&self, num_range: u32, stack: &[u32], offset: bool) -> VertexIndexPair {
+    /// Horizontal extent of the bar of a number, relative to the left side of its stack.
+    fn bar_extent(&self, sim: &PushSwapSim, number: u32) -> (f32, f32) {
+        let num_range = sim.amount() as f32;
+        let originals = sim.original_values();
+        if self.visual.proportional_bars()
+            && let (Some(min), Some(max), Some(value)) = (
+                originals.first(),
+                originals.last(),
+                originals.get(number as usize),
+            )
+        {
+            let low = (*min).min(0) as f64;
+            let high = (*max).max(0) as f64;
+            let span = (high - low).max(1.);
+            let zero = (-low / span) as f32 * num_range;
+            let len = (*value as f64 / span) as f32 * num_range;
+            let min_len = MIN_PROPORTIONAL_BAR * num_range;
+            return if *value < 0 {
+                (zero + len.min(-min_len), zero)
+            } else {
+                (zero, zero + len.max(min_len))
+            };
+        }
+        (0., number as f32 + 1.)
+    }
+
+    fn generate_sim_tris_data(&self, sim: &PushSwapSim, offset: f32) -> VertexIndexPair {
+        let (stack_a, stack_b) = sim.stacks();
+        let tracked = self.playback.tracked();
+        let mut data = self.generate_tris_data(sim, stack_a.iter(), offset, tracked);
+        data.extend(self.generate_tris_data(
+            sim,
+            stack_b.iter(),
+            offset + sim.amount() as f32,
+            tracked,
+        ));
+        data
+    }
+
+    fn generate_tris_data<'a>(
+        &self,
+        sim: &PushSwapSim,
+        stack: impl Iterator<Item = &'a u32>,
+        offset: f32,
+        tracked: Option<u32>,
+    ) -> VertexIndexPair {
+        let num_range = sim.amount() as f32;
         let mut vertices = vec![];
         let mut indices = vec![];
         let mut next_index = 0;
-        for (i, num) in stack.iter().enumerate() {
+        for (i, num) in stack.enumerate() {
             let i = i as f32;
-            let num = (*num) as f32;
-            let o = if offset { num_range as f32 } else { 0. };
-            let t = num / num_range as f32;
-            let color = self.visual.color_at(t);
+            let highlight = tracked == Some(*num);
+            let (start, end) = self.bar_extent(sim, *num);
+            let t = (*num) as f32 / num_range;
+            let mut color = self.visual.color_at(t);
+            if highlight {
+                // Inverted colors stand out from the gradient whatever its colors are
+                color = [1. - color[0], 1. - color[1], 1. - color[2], color[3]];
+            }
             vertices.push(Vertex {
-                position: [0.0 + o, i, 0.0],
+                position: [start + offset, i, 0.0],
                 color,
             });
             vertices.push(Vertex {
-                position: [num + 1.0 + o, i, 0.0],
+                position: [end + offset, i, 0.0],
                 color,
             });
             vertices.push(Vertex {
-                position: [num + 1.0 + o, i + 1.0, 0.0],
+                position: [end + offset, i + 1.0, 0.0],
                 color,
             });
             vertices.push(Vertex {
-                position: [0.0 + o, i + 1.0, 0.0],
+                position: [start + offset, i + 1.0, 0.0],
                 color,
             });
             indices.extend_from_slice(&[
@@ -160,22 +333,29 @@ impl SortView {
                 ui.checkbox(&mut self.show_playback, "Show Playback Controls").on_hover_text("Shows a floating window with playback controls and a table of running instructions.");
                 ui.small(format!("Running psv v{}", env!("CARGO_PKG_VERSION")));
             });
+        let proportional_bars = self.visual.proportional_bars();
         self.visual.ui(ui, &mut self.config, &mut self.show_visual);
-        self.load.ui(
+        self.regenerate_render_data |= proportional_bars != self.visual.proportional_bars();
+        let sim_changed = self.load.ui(
             ui,
             &mut self.config,
             &mut self.show_load,
             &mut self.sim,
+            &mut self.comparison,
             &mut self.regenerate_render_data,
-            &mut self.projection,
             &mut self.playing_sim,
             &mut self.show_playback,
         );
+        if sim_changed {
+            self.playback.sim_changed(&self.sim);
+            self.update_projection();
+        }
         let mut temp_stop = false;
         self.playback.ui(
             ui,
             &mut self.show_playback,
             &mut self.sim,
+            &mut self.comparison,
             &mut self.playing_sim,
             &mut temp_stop,
             &mut self.exec_interval,
@@ -184,15 +364,23 @@ impl SortView {
         if self.playing_sim && !temp_stop {
             let current_instant = Instant::now();
             let catching_duration = current_instant.duration_since(self.last_instant);
-            while self.duration_accumulated <= catching_duration {
-                self.sim.step();
+            let mut breakpoint_hit = false;
+            while !breakpoint_hit && self.duration_accumulated <= catching_duration {
+                breakpoint_hit = self.sim.step() && self.playback.breakpoint_hit(&self.sim);
                 self.regenerate_render_data = true;
                 self.duration_accumulated += self.exec_interval;
             }
-            self.duration_accumulated -= catching_duration;
+            if breakpoint_hit {
+                self.playing_sim = false;
+                self.duration_accumulated = Duration::ZERO;
+            } else {
+                self.duration_accumulated -= catching_duration;
+            }
         } else {
             self.duration_accumulated = Duration::ZERO;
         }
         self.last_instant = Instant::now();
+        self.sync_comparison();
+        self.tracking_ui(ui);
     }
 }
@@ -0,0 +1,213 @@
+//! Statistics about the instructions used by a push_swap program.
+
+use crate::sim::{PushSwapInstruction, PushSwapSim, Stack};
+
+/// Breakdown of a list of instructions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InstructionStats {
+    /// Amount of each instruction, in the order of [`PushSwapInstruction::ALL`].
+    pub counts: [usize; PushSwapInstruction::ALL.len()],
+    /// Amount of instructions.
+    pub total: usize,
+    /// Amount of numbers that were pushed to stack B and back to stack A. Pushes
+    /// from an empty stack do nothing, so they are not counted.
+    pub push_pairs: usize,
+    /// Longest run of the same rotation instruction.
+    pub longest_rotation: Option<(PushSwapInstruction, usize)>,
+}
+
+fn is_rotation(ins: PushSwapInstruction) -> bool {
+    use PushSwapInstruction::*;
+    matches!(
+        ins,
+        RotateA | RotateB | RotateBoth | ReverseRotateA | ReverseRotateB | ReverseRotateBoth
+    )
+}
+
+impl InstructionStats {
+    /// Breaks down instructions sorting `amount` numbers, which all start on stack A.
+    pub fn new(instructions: &[PushSwapInstruction], amount: usize) -> Self {
+        let mut stats = Self {
+            total: instructions.len(),
+            ..Default::default()
+        };
+        let mut run: Option<(PushSwapInstruction, usize)> = None;
+        let (mut size_a, mut size_b) = (amount, 0);
+        for &ins in instructions {
+            stats.counts[ins as usize] += 1;
+            match ins {
+                PushSwapInstruction::PushA if size_b > 0 => {
+                    (size_a, size_b) = (size_a + 1, size_b - 1);
+                    stats.push_pairs += 1;
+                }
+                PushSwapInstruction::PushB if size_a > 0 => {
+                    (size_a, size_b) = (size_a - 1, size_b + 1);
+                }
+                _ => {}
+            }
+            run = match run {
+                Some((prev, len)) if prev == ins => Some((ins, len + 1)),
+                _ if is_rotation(ins) => Some((ins, 1)),
+                _ => None,
+            };
+            if let Some((_, len)) = run
+                && stats
+                    .longest_rotation
+                    .is_none_or(|(_, longest)| len > longest)
+            {
+                stats.longest_rotation = run;
+            }
+        }
+        stats
+    }
+
+    /// Adds the counts of another breakdown to this one.
+    pub fn merge(&mut self, other: &InstructionStats) {
+        for (count, other) in self.counts.iter_mut().zip(other.counts) {
+            *count += other;
+        }
+        self.total += other.total;
+        self.push_pairs += other.push_pairs;
+        if let Some((_, len)) = other.longest_rotation
+            && self
+                .longest_rotation
+                .is_none_or(|(_, longest)| len > longest)
+        {
+            self.longest_rotation = other.longest_rotation;
+        }
+    }
+
+    /// Amount of the given instruction.
+    pub fn count(&self, ins: PushSwapInstruction) -> usize {
+        self.counts[ins as usize]
+    }
+
+    /// Share of the given amount of instructions, from 0 to 1.
+    pub fn share(&self, count: usize) -> f64 {
+        if self.total == 0 {
+            0.
+        } else {
+            count as f64 / self.total as f64
+        }
+    }
+
+    /// Amount of `ss`, `rr` and `rrr` instructions.
+    pub fn combined(&self) -> usize {
+        use PushSwapInstruction::*;
+        self.count(SwapBoth) + self.count(RotateBoth) + self.count(ReverseRotateBoth)
+    }
+}
+
+/// How many times a number was moved by each kind of instruction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NumberMoves {
+    /// Amount of `pa` and `pb` that moved the number to the other stack.
+    pub pushes: usize,
+    /// Amount of rotations that moved the number from one end of its stack to the other.
+    pub rotations: usize,
+    /// Amount of swaps that exchanged the number with its neighbour.
+    pub swaps: usize,
+}
+
+impl NumberMoves {
+    fn count(&mut self, number: u32, ins: PushSwapInstruction, stack_a: &Stack, stack_b: &Stack) {
+        use PushSwapInstruction::*;
+        let swapped = |stack: &Stack| stack.len() >= 2 && stack.range(..2).any(|n| *n == number);
+        let rotated = |stack: &Stack| stack.len() >= 2 && stack.front() == Some(&number);
+        let reverse_rotated = |stack: &Stack| stack.len() >= 2 && stack.back() == Some(&number);
+        let moved = match ins {
+            SwapA => (false, false, swapped(stack_a)),
+            SwapB => (false, false, swapped(stack_b)),
+            SwapBoth => (false, false, swapped(stack_a) || swapped(stack_b)),
+            PushA => (stack_b.front() == Some(&number), false, false),
+            PushB => (stack_a.front() == Some(&number), false, false),
+            RotateA => (false, rotated(stack_a), false),
+            RotateB => (false, rotated(stack_b), false),
+            RotateBoth => (false, rotated(stack_a) || rotated(stack_b), false),
+            ReverseRotateA => (false, reverse_rotated(stack_a), false),
+            ReverseRotateB => (false, reverse_rotated(stack_b), false),
+            ReverseRotateBoth => (
+                false,
+                reverse_rotated(stack_a) || reverse_rotated(stack_b),
+                false,
+            ),
+        };
+        self.pushes += moved.0 as usize;
+        self.rotations += moved.1 as usize;
+        self.swaps += moved.2 as usize;
+    }
+}
+
+/// Counts the moves of a number before the first instruction and after every
+/// instruction of the simulation, regardless of its current program counter.
+pub fn number_moves(sim: &PushSwapSim, number: u32) -> Vec<NumberMoves> {
+    let mut stack_a = Stack::from(sim.initial_numbers().to_vec());
+    let mut stack_b = Stack::new();
+    let mut moves = NumberMoves::default();
+    let mut steps = Vec::with_capacity(sim.instructions().len() + 1);
+    steps.push(moves);
+    for &ins in sim.instructions() {
+        moves.count(number, ins, &stack_a, &stack_b);
+        ins.execute(&mut stack_a, &mut stack_b);
+        steps.push(moves);
+    }
+    steps
+}
+
+#[cfg(test)]
+mod test {
+    use super::{InstructionStats, NumberMoves, number_moves};
+    use crate::{
+        parser::parse_push_swap,
+        sim::{PushSwapInstruction::*, PushSwapSim},
+    };
+
+    #[test]
+    fn breakdown() {
+        let ins = parse_push_swap("pb pb ra ra ra rr rra rra pa ss pa pa rrr").unwrap();
+        let stats = InstructionStats::new(&ins, 5);
+        assert_eq!(stats.total, 13);
+        assert_eq!(stats.count(RotateA), 3);
+        assert_eq!(stats.count(PushA), 3);
+        assert_eq!(stats.combined(), 3);
+        assert_eq!(stats.push_pairs, 2);
+        assert_eq!(stats.longest_rotation, Some((RotateA, 3)));
+
+        // Pushes from an empty stack are no-ops
+        let ins = parse_push_swap("pa pb pb pa pa pa").unwrap();
+        assert_eq!(InstructionStats::new(&ins, 1).push_pairs, 1);
+    }
+
+    #[test]
+    fn moves() {
+        let mut sim = PushSwapSim::default();
+        sim.load_normalized(vec![2, 0, 1], "sa pb ra rra pa rr")
+            .unwrap();
+        let moves = number_moves(&sim, 0);
+        assert_eq!(moves.len(), 7);
+        assert_eq!(
+            moves[2],
+            NumberMoves {
+                pushes: 1,
+                rotations: 0,
+                swaps: 1
+            }
+        );
+        assert_eq!(
+            moves[6],
+            NumberMoves {
+                pushes: 2,
+                rotations: 1,
+                swaps: 1
+            }
+        );
+        assert_eq!(
+            number_moves(&sim, 2)[6],
+            NumberMoves {
+                pushes: 0,
+                rotations: 2,
+                swaps: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Export of the state of the stacks after every instruction of a run.
+
+use std::{
+    fmt::Display,
+    io::{self, Write},
+    path::Path,
+};
+
+use serde::Serialize;
+
+use crate::sim::{PushSwapSim, Stack};
+
+/// File format of a step trace.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// One JSON object per line.
+    #[default]
+    JsonLines,
+    /// Comma-separated values with a header line. Numbers in a stack are separated by spaces,
+    /// and the original values are left empty when they are unknown.
+    Csv,
+}
+
+impl TraceFormat {
+    /// Every trace format.
+    pub const ALL: [TraceFormat; 2] = [TraceFormat::JsonLines, TraceFormat::Csv];
+
+    /// File extension of the format, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            TraceFormat::JsonLines => "jsonl",
+            TraceFormat::Csv => "csv",
+        }
+    }
+
+    /// Guesses the format from the extension of a path, defaulting to JSON Lines.
+    pub fn from_path(path: &Path) -> Self {
+        let is_csv = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        if is_csv {
+            TraceFormat::Csv
+        } else {
+            TraceFormat::JsonLines
+        }
+    }
+}
+
+impl Display for TraceFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            TraceFormat::JsonLines => "JSON Lines",
+            TraceFormat::Csv => "CSV",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// State of the stacks after an instruction was executed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TraceStep<'a> {
+    /// Amount of instructions executed so far.
+    pub program_counter: usize,
+    /// The last executed instruction, if any.
+    pub instruction: Option<String>,
+    /// Stack A, top first.
+    pub stack_a: &'a [u32],
+    /// Stack B, top first.
+    pub stack_b: &'a [u32],
+    /// Amount of numbers in stack A.
+    pub size_a: usize,
+    /// Amount of numbers in stack B.
+    pub size_b: usize,
+    /// Stack A before normalization, if the original values are known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_a: Option<Vec<i64>>,
+    /// Stack B before normalization, if the original values are known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_b: Option<Vec<i64>>,
+}
+
+fn join<T: ToString>(stack: &[T]) -> String {
+    stack.iter().map(T::to_string).collect::<Vec<_>>().join(" ")
+}
+
+fn write_step(step: &TraceStep, format: TraceFormat, writer: &mut impl Write) -> io::Result<()> {
+    match format {
+        TraceFormat::JsonLines => {
+            serde_json::to_writer(&mut *writer, step)?;
+            writeln!(writer)
+        }
+        TraceFormat::Csv => writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{}",
+            step.program_counter,
+            step.instruction.as_deref().unwrap_or(""),
+            join(step.stack_a),
+            join(step.stack_b),
+            step.size_a,
+            step.size_b,
+            step.original_a.as_deref().map(join).unwrap_or_default(),
+            step.original_b.as_deref().map(join).unwrap_or_default()
+        ),
+    }
+}
+
+/// Writes the state of the stacks before the first instruction and after every
+/// instruction of the simulation, regardless of its current program counter.
+pub fn write_trace(
+    sim: &PushSwapSim,
+    format: TraceFormat,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    if format == TraceFormat::Csv {
+        writeln!(
+            writer,
+            "program_counter,instruction,stack_a,stack_b,size_a,size_b,original_a,original_b"
+        )?;
+    }
+    let mut stack_a = Stack::from(sim.initial_numbers().to_vec());
+    let mut stack_b = Stack::new();
+    for program_counter in 0..=sim.instructions().len() {
+        let instruction = program_counter.checked_sub(1).map(|i| {
+            let ins = sim.instructions()[i];
+            ins.execute(&mut stack_a, &mut stack_b);
+            ins.to_string()
+        });
+        let (size_a, size_b) = (stack_a.len(), stack_b.len());
+        let originals = sim.original_values();
+        let original = |stack: &Stack| {
+            (!originals.is_empty()).then(|| stack.iter().map(|n| originals[*n as usize]).collect())
+        };
+        let (original_a, original_b) = (original(&stack_a), original(&stack_b));
+        let step = TraceStep {
+            program_counter,
+            instruction,
+            stack_a: stack_a.make_contiguous(),
+            stack_b: stack_b.make_contiguous(),
+            size_a,
+            size_b,
+            original_a,
+            original_b,
+        };
+        write_step(&step, format, &mut writer)?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{TraceFormat, write_trace};
+    use crate::sim::PushSwapSim;
+
+    #[test]
+    fn test_trace() {
+        let mut sim = PushSwapSim::default();
+        sim.load_normalized(vec![1, 0, 2], "pb\npa\nsa\n").unwrap();
+
+        let mut csv = vec![];
+        write_trace(&sim, TraceFormat::Csv, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "program_counter,instruction,stack_a,stack_b,size_a,size_b,original_a,original_b\n\
+            0,,1 0 2,,3,0,,\n\
+            1,pb,0 2,1,2,1,,\n\
+            2,pa,1 0 2,,3,0,,\n\
+            3,sa,0 1 2,,3,0,,\n"
+        );
+
+        let mut jsonl = vec![];
+        write_trace(&sim, TraceFormat::JsonLines, &mut jsonl).unwrap();
+        let jsonl = String::from_utf8(jsonl).unwrap();
+        let lines: Vec<_> = jsonl.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[1],
+            r#"{"program_counter":1,"instruction":"pb","stack_a":[0,2],"stack_b":[1],"size_a":2,"size_b":1}"#
+        );
+
+        sim.load_random(&[5, -3, 42], "pb\n").unwrap();
+        let mut csv = vec![];
+        write_trace(&sim, TraceFormat::Csv, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().nth(2), Some("1,pb,0 2,1,2,1,-3 42,5"));
+    }
+}